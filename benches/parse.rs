@@ -0,0 +1,66 @@
+use mycelium_uns_rs::{MyceliumSubject, MyceliumSubjectRef};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const SUBJECT: &str =
+    "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+const ITERATIONS: usize = 100_000;
+
+fn bench(name: &str, f: impl Fn()) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    println!(
+        "{name}: {:?}/iter, {} allocations/iter",
+        elapsed / ITERATIONS as u32,
+        allocations / ITERATIONS
+    );
+}
+
+fn main() {
+    bench("MyceliumSubject::from_str", || {
+        black_box(MyceliumSubject::from_str(black_box(SUBJECT)).unwrap());
+    });
+    bench("MyceliumSubjectRef::parse", || {
+        black_box(MyceliumSubjectRef::parse(black_box(SUBJECT)).unwrap());
+    });
+    bench("MyceliumSubject payload identifiers", || {
+        let subject = MyceliumSubject::from_str(black_box(SUBJECT)).unwrap();
+        for segment in &subject.payload_identifier {
+            black_box(segment);
+        }
+    });
+    bench("MyceliumSubjectRef payload identifiers", || {
+        let subject = MyceliumSubjectRef::parse(black_box(SUBJECT)).unwrap();
+        for segment in subject.payload_identifier() {
+            black_box(segment);
+        }
+    });
+}
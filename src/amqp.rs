@@ -0,0 +1,60 @@
+//! Routing keys for AMQP topic exchanges.
+//!
+//! AMQP uses the same `.` separator and `*` single-word wildcard as NATS. Its multi-word
+//! wildcard `#` matches zero or more words, whereas NATS `>` matches one or more, so a plain
+//! `>` to `#` swap would also deliver the parent subject. Patterns therefore map `>` to
+//! `*.#`, which keeps the one-or-more meaning.
+
+use crate::{MyceliumSubject, SubjectPattern, Token};
+use alloc::string::String;
+
+impl MyceliumSubject {
+    /// The routing key to publish this subject on, identical to its canonical string.
+    pub fn to_amqp_routing_key(&self) -> String {
+        String::from(self)
+    }
+}
+
+impl SubjectPattern {
+    /// The binding key matching the same subjects as this pattern.
+    pub fn to_amqp_routing_key(&self) -> String {
+        let mut key = String::new();
+        for (index, token) in self.tokens().iter().enumerate() {
+            if index > 0 {
+                key.push('.');
+            }
+            match token {
+                Token::Literal(literal) => key.push_str(literal),
+                Token::Wildcard => key.push('*'),
+                Token::MultiWildcard => key.push_str("*.#"),
+            }
+        }
+        key
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use core::str::FromStr;
+
+    #[test]
+    fn amqp_routing_key_subject() {
+        let subject_string = "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.value";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(subject_string, subject.to_amqp_routing_key());
+    }
+
+    #[test]
+    fn amqp_routing_key_pattern() {
+        let pattern = SubjectPattern::from_str("prod.abc.xyz.local.*.*.data").unwrap();
+        assert_eq!(pattern.to_string(), pattern.to_amqp_routing_key());
+
+        let pattern = SubjectPattern::from_str("prod.abc.xyz.local.plc-gateway.1.>").unwrap();
+        assert_eq!(
+            "prod.abc.xyz.local.plc-gateway.1.*.#",
+            pattern.to_amqp_routing_key()
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    Environment, GeoLocator, InstanceId, Locator, MyceliumSubject, OwnershipGroup, PayloadPath,
+    PayloadType, ServiceIdentifier, known_payload_type,
+};
+use alloc::{string::String, vec::Vec};
+use proptest::{collection::vec, prelude::*, sample::select};
+
+/// Real ISO 3166-2 codes, so generated locators pass the `strict-iso` lookup.
+const ISO_CODES: [&str; 8] = [
+    "US-CA", "US-TX", "CA-QC", "CA-ON", "DE-BY", "FR-IDF", "JP-13", "BR-SP",
+];
+
+/// A plain token. It has no dash, so it never looks like an ISO code, which matters for
+/// ownership levels.
+fn level() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,9}".prop_filter(
+        "geo keywords and payload types end the ownership group",
+        |level| {
+            !["local", "global", "unknown"].contains(&level.as_str())
+                && known_payload_type(level).is_none()
+        },
+    )
+}
+
+/// A token that never names a payload type, which would change where a locator region ends.
+fn token() -> impl Strategy<Value = String> {
+    "[a-z0-9][a-z0-9_-]{0,11}".prop_filter("payload types end the locator region", |token| {
+        known_payload_type(token).is_none()
+    })
+}
+
+fn geo_locator() -> impl Strategy<Value = GeoLocator> {
+    prop_oneof![
+        Just(GeoLocator::Local),
+        Just(GeoLocator::Global),
+        Just(GeoLocator::Unknown),
+        (select(&ISO_CODES[..]), vec(token(), 1..=2), token()).prop_map(
+            |(iso_3166_2, op_region, op_identifier)| {
+                GeoLocator::Locator(Locator {
+                    iso_3166_2: iso_3166_2.into(),
+                    op_region,
+                    op_identifier,
+                })
+            }
+        ),
+    ]
+}
+
+impl Arbitrary for MyceliumSubject {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let payload_types = [
+            PayloadType::Heartbeat,
+            PayloadType::Data,
+            PayloadType::Diagnostics,
+            PayloadType::Command,
+            PayloadType::Event,
+        ];
+        (
+            select(&Environment::ALL[..]),
+            vec(level(), 2..5),
+            geo_locator(),
+            (token(), token()),
+            select(Vec::from(payload_types)),
+            vec(token(), 0..6),
+        )
+            .prop_map(
+                |(
+                    environment,
+                    levels,
+                    geo_locator,
+                    (service_name, instance_id),
+                    payload_type,
+                    payload_identifier,
+                )| {
+                    MyceliumSubject {
+                        environment,
+                        ownership_group: OwnershipGroup { levels },
+                        geo_locator,
+                        service_identifier: ServiceIdentifier {
+                            service_name,
+                            instance_id: InstanceId(instance_id),
+                        },
+                        payload_type,
+                        payload_identifier: PayloadPath(payload_identifier),
+                    }
+                },
+            )
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use core::str::FromStr;
+
+    proptest! {
+        #[test]
+        fn subject_round_trip(subject: MyceliumSubject) {
+            let subject_string = subject.to_string();
+            prop_assert_eq!(subject, MyceliumSubject::from_str(&subject_string).unwrap());
+        }
+    }
+}
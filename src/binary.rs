@@ -0,0 +1,289 @@
+use crate::{
+    Environment, GeoLocator, Locator, MyceliumSubject, OwnershipGroup, ParseError, PayloadPath,
+    PayloadType, ServiceIdentifier, validate_token,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
+
+const LOCAL: u8 = 0;
+const GLOBAL: u8 = 1;
+const UNKNOWN: u8 = 2;
+const LOCATOR: u8 = 3;
+const OMITTED: u8 = 4;
+
+const PAYLOAD_TYPES: [PayloadType; 5] = [
+    PayloadType::Heartbeat,
+    PayloadType::Data,
+    PayloadType::Diagnostics,
+    PayloadType::Command,
+    PayloadType::Event,
+];
+const CUSTOM: u8 = PAYLOAD_TYPES.len() as u8;
+
+fn write_varint(buf: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn write_token(buf: &mut Vec<u8>, token: &str) {
+    write_varint(buf, token.len());
+    buf.extend_from_slice(token.as_bytes());
+}
+
+fn write_tokens(buf: &mut Vec<u8>, tokens: &[String]) {
+    write_varint(buf, tokens.len());
+    tokens.iter().for_each(|token| write_token(buf, token));
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, ParseError> {
+        let byte = *self
+            .bytes
+            .get(self.offset)
+            .ok_or(ParseError::InvalidEncoding(self.offset))?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<usize, ParseError> {
+        let start = self.offset;
+        let mut value = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.byte()?;
+            value |= usize::from(byte & 0x7f)
+                .checked_shl(shift)
+                .ok_or(ParseError::InvalidEncoding(start))?;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ParseError::InvalidEncoding(start))
+    }
+
+    /// A token, checked for UTF-8 and validated like a parsed token.
+    fn token(&mut self) -> Result<&'a str, ParseError> {
+        let len = self.varint()?;
+        let start = self.offset;
+        let bytes = start
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(start..end))
+            .ok_or(ParseError::InvalidEncoding(start))?;
+        self.offset += len;
+        let token = core::str::from_utf8(bytes).map_err(ParseError::InvalidUtf8)?;
+        validate_token(token)?;
+        Ok(token)
+    }
+
+    fn tokens(&mut self) -> Result<Vec<String>, ParseError> {
+        let start = self.offset;
+        let count = self.varint()?;
+        // Every token takes at least a byte, which bounds the allocation.
+        if count > self.bytes.len() - self.offset {
+            return Err(ParseError::InvalidEncoding(start));
+        }
+        (0..count)
+            .map(|_| self.token().map(str::to_string))
+            .collect()
+    }
+}
+
+impl MyceliumSubject {
+    /// A length-prefixed binary form, smaller and faster to decode than the dotted string.
+    ///
+    /// Counts and lengths are unsigned LEB128 varints and every token is its length followed
+    /// by its UTF-8 bytes. The layout is
+    ///
+    /// - the environment, one byte
+    /// - the number of ownership levels, then each level
+    /// - the geo scope, one byte: `0` local, `1` global, `2` unknown, `3` locator, which is
+    ///   followed by the ISO code, the number of region segments, each segment and the
+    ///   identifier, or `4` omitted
+    /// - the service name and the instance id
+    /// - the payload type, one byte, followed by its token for a custom payload type
+    /// - the number of payload identifiers, then each identifier
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.serialized_len());
+        let environment = Environment::ALL
+            .iter()
+            .position(|environment| *environment == self.environment)
+            .expect("every environment is listed");
+        buf.push(environment as u8);
+        write_tokens(&mut buf, self.ownership_group.levels());
+        match &self.geo_locator {
+            GeoLocator::Local => buf.push(LOCAL),
+            GeoLocator::Global => buf.push(GLOBAL),
+            GeoLocator::Unknown => buf.push(UNKNOWN),
+            GeoLocator::Locator(locator) => {
+                buf.push(LOCATOR);
+                write_token(&mut buf, locator.iso_3166_2());
+                write_tokens(&mut buf, locator.op_region());
+                write_token(&mut buf, locator.op_identifier());
+            }
+            GeoLocator::Omitted => buf.push(OMITTED),
+        }
+        write_token(&mut buf, self.service_identifier.service_name());
+        write_token(&mut buf, self.service_identifier.instance_id());
+        match PAYLOAD_TYPES.iter().position(|p| *p == self.payload_type) {
+            Some(payload_type) => buf.push(payload_type as u8),
+            None => {
+                buf.push(CUSTOM);
+                write_token(&mut buf, self.payload_type.as_str());
+            }
+        }
+        write_tokens(&mut buf, self.payload_identifier.segments());
+        buf
+    }
+
+    /// Decodes the output of [`Self::to_bytes`].
+    ///
+    /// Every token is checked for UTF-8 and validated as when parsing, and the ISO code is
+    /// uppercased, but it is trusted to name a known subdivision, since looking it up is the
+    /// expensive part of parsing. Malformed framing is reported as [`ParseError::InvalidEncoding`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        let environment = *Environment::ALL
+            .get(usize::from(reader.byte()?))
+            .ok_or(ParseError::InvalidEncoding(0))?;
+        let ownership_group = OwnershipGroup::new(reader.tokens()?)?;
+        let geo_offset = reader.offset;
+        let geo_locator = match reader.byte()? {
+            LOCAL => GeoLocator::Local,
+            GLOBAL => GeoLocator::Global,
+            UNKNOWN => GeoLocator::Unknown,
+            OMITTED => GeoLocator::Omitted,
+            LOCATOR => {
+                let iso_3166_2 = reader.token()?;
+                let op_region = reader.tokens()?;
+                let op_identifier = reader.token()?;
+                GeoLocator::Locator(Locator::with_trusted_iso_code(
+                    iso_3166_2,
+                    op_region,
+                    op_identifier,
+                )?)
+            }
+            _ => return Err(ParseError::InvalidEncoding(geo_offset)),
+        };
+        let service_identifier = ServiceIdentifier::new(reader.token()?, reader.token()?)?;
+        let payload_offset = reader.offset;
+        let payload_type = match reader.byte()? {
+            CUSTOM => PayloadType::from_str(reader.token()?)?,
+            payload_type => PAYLOAD_TYPES
+                .get(usize::from(payload_type))
+                .cloned()
+                .ok_or(ParseError::InvalidEncoding(payload_offset))?,
+        };
+        let payload_identifier = PayloadPath::new(reader.tokens()?)?;
+        if reader.offset != bytes.len() {
+            return Err(ParseError::InvalidEncoding(reader.offset));
+        }
+        Ok(MyceliumSubject {
+            environment,
+            ownership_group,
+            geo_locator,
+            service_identifier,
+            payload_type,
+            payload_identifier,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+            "staging.abc.north.xyz.US-CA.north.bay-area.abc.plc-gateway.1.event",
+            "dev.abc.xyz.local.plc-gateway.1.heartbeat",
+            "qa.abc.xyz.global.scheduler.2.command.restart",
+            "test.abc.xyz.unknown.plc-gateway.1.diagnostics.custom",
+            "prod.abc.xyz.local.plc-gateway.1.custom.value",
+        ];
+        for subject_string in subject_strings {
+            let subject = MyceliumSubject::from_str(subject_string).unwrap();
+            let bytes = subject.to_bytes();
+            assert!(bytes.len() < subject_string.len());
+            assert_eq!(subject, MyceliumSubject::from_bytes(&bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn from_bytes_fail() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.sensor").unwrap();
+        let bytes = subject.to_bytes();
+        assert_eq!(
+            Err(ParseError::InvalidEncoding(bytes.len() - "sensor".len())),
+            MyceliumSubject::from_bytes(&bytes[..bytes.len() - 1])
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Err(ParseError::InvalidEncoding(bytes.len())),
+            MyceliumSubject::from_bytes(&trailing)
+        );
+
+        let mut environment = bytes.clone();
+        environment[0] = 9;
+        assert_eq!(
+            Err(ParseError::InvalidEncoding(0)),
+            MyceliumSubject::from_bytes(&environment)
+        );
+
+        assert_eq!(
+            Err(ParseError::InvalidEncoding(0)),
+            MyceliumSubject::from_bytes(&[])
+        );
+    }
+
+    #[test]
+    fn from_bytes_custom_tag_with_known_payload_type() {
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.custom").unwrap();
+        let bytes = subject.to_bytes();
+        // The custom token is followed by the payload identifier count.
+        let custom_len = bytes.len() - "custom".len() - 2;
+        for known in ["data", "DATA"] {
+            let mut patched = bytes[..custom_len].to_vec();
+            patched.push(known.len() as u8);
+            patched.extend_from_slice(known.as_bytes());
+            patched.push(0);
+            let decoded = MyceliumSubject::from_bytes(&patched).unwrap();
+            assert_eq!(PayloadType::Data, decoded.payload_type);
+        }
+    }
+
+    #[test]
+    fn from_bytes_normalizes_iso_code() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data").unwrap();
+        let mut bytes = subject.to_bytes();
+        let iso = bytes
+            .windows(5)
+            .position(|window| window == b"US-CA")
+            .unwrap();
+        bytes[iso..iso + 5].copy_from_slice(b"us-ca");
+        let decoded = MyceliumSubject::from_bytes(&bytes).unwrap();
+        assert_eq!(subject, decoded);
+        assert_eq!(subject.stable_hash(), decoded.stable_hash());
+
+        bytes[iso..iso + 5].copy_from_slice(b"usxca");
+        assert_eq!(
+            Err(ParseError::InvalidIsoCode("usxca".to_string())),
+            MyceliumSubject::from_bytes(&bytes)
+        );
+    }
+}
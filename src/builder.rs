@@ -0,0 +1,485 @@
+use crate::{
+    Environment, GeoLocator, InstanceIdFormat, MyceliumSubject, MyceliumSubjectRef, OwnershipGroup,
+    ParseError, PayloadPath, PayloadType, SUBJECT_SEPARATOR, ServiceIdentifier,
+    validate_not_reserved,
+};
+use alloc::{format, string::String, vec::Vec};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq, Clone)]
+pub enum BuildError {
+    #[error("missing required field: {0}")]
+    MissingField(&'static str),
+    #[error("invalid payload identifier: {0}")]
+    InvalidPayloadIdentifier(ParseError),
+    #[error("invalid subject: {0}")]
+    InvalidSubject(ParseError),
+    #[error("ISO 3166-2 code {0:?} is not in the allow-list")]
+    DisallowedIsoCode(String),
+}
+
+/// Fluent builder for [`MyceliumSubject`].
+///
+/// The builder is `Clone`, so a partially filled builder can serve as a template
+/// for many subjects that only differ in their payload. A new builder starts with the
+/// environment set to [`Environment::default`] and every other field empty.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MyceliumSubjectBuilder {
+    environment: Option<Environment>,
+    ownership_group: Option<OwnershipGroup>,
+    geo_locator: Option<GeoLocator>,
+    service_identifier: Option<ServiceIdentifier>,
+    payload_type: Option<PayloadType>,
+    payload_identifier: Vec<String>,
+    max_length: Option<usize>,
+    max_payload_depth: Option<usize>,
+    reject_reserved: bool,
+    allowed_iso_codes: Option<Vec<String>>,
+    instance_id_format: InstanceIdFormat,
+}
+
+impl Default for MyceliumSubjectBuilder {
+    fn default() -> Self {
+        MyceliumSubjectBuilder {
+            environment: Some(Environment::default()),
+            ownership_group: None,
+            geo_locator: None,
+            service_identifier: None,
+            payload_type: None,
+            payload_identifier: Vec::new(),
+            max_length: None,
+            max_payload_depth: None,
+            reject_reserved: false,
+            allowed_iso_codes: None,
+            instance_id_format: InstanceIdFormat::Any,
+        }
+    }
+}
+
+impl MyceliumSubjectBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a builder from a subject prefix, leaving the payload type and identifiers unset.
+    ///
+    /// A valid prefix holds exactly the environment, ownership group, geo-locator, service name
+    /// and instance id, like `prod.abc.xyz.local.plc-gateway.1`. It is validated the same way
+    /// as the start of a full subject.
+    pub fn from_prefix(prefix: &str) -> Result<Self, ParseError> {
+        let subject_string = format!("{prefix}{SUBJECT_SEPARATOR}{}", PayloadType::Heartbeat);
+        let subject = MyceliumSubjectRef::parse(&subject_string).map_err(|error| match error {
+            ParseError::TooFewTokens { expected, found } => ParseError::TooFewTokens {
+                expected: expected - PayloadType::TOKENS,
+                found: found - PayloadType::TOKENS,
+            },
+            error => error,
+        })?;
+        let depth = subject.payload_identifier().len();
+        if depth > 0 {
+            let found = subject_string.split(SUBJECT_SEPARATOR).count() - PayloadType::TOKENS;
+            return Err(ParseError::TooManyTokens {
+                expected: found - depth,
+                found,
+            });
+        }
+        let subject = subject.to_owned();
+        Ok(Self::new()
+            .environment(subject.environment)
+            .ownership_group(subject.ownership_group)
+            .geo_locator(subject.geo_locator)
+            .service_identifier(subject.service_identifier))
+    }
+
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    pub fn ownership_group(mut self, ownership_group: OwnershipGroup) -> Self {
+        self.ownership_group = Some(ownership_group);
+        self
+    }
+
+    pub fn geo_locator(mut self, geo_locator: GeoLocator) -> Self {
+        self.geo_locator = Some(geo_locator);
+        self
+    }
+
+    pub fn service_identifier(mut self, service_identifier: ServiceIdentifier) -> Self {
+        self.service_identifier = Some(service_identifier);
+        self
+    }
+
+    pub fn payload_type(mut self, payload_type: PayloadType) -> Self {
+        self.payload_type = Some(payload_type);
+        self
+    }
+
+    pub fn push_payload_identifier(mut self, payload_identifier: impl Into<String>) -> Self {
+        self.payload_identifier.push(payload_identifier.into());
+        self
+    }
+
+    /// Makes [`build`](Self::build) fail when the subject would exceed `max_bytes`.
+    pub fn max_length(mut self, max_bytes: usize) -> Self {
+        self.max_length = Some(max_bytes);
+        self
+    }
+
+    /// Overrides [`PayloadPath::DEFAULT_MAX_DEPTH`] for the built subject.
+    ///
+    /// A subject deeper than the default would not parse back, so only a lower limit has an
+    /// effect.
+    pub fn max_payload_depth(mut self, max_depth: usize) -> Self {
+        self.max_payload_depth = Some(max_depth);
+        self
+    }
+
+    /// Makes [`build`](Self::build) reject payload identifiers equal to `local`, `global`, `unknown` or
+    /// an environment name.
+    pub fn reject_reserved_payload_identifiers(mut self) -> Self {
+        self.reject_reserved = true;
+        self
+    }
+
+    /// Makes [`build`](Self::build) reject locators whose ISO 3166-2 code is not one of
+    /// `codes`, ignoring ASCII case. `local`, `global` and `unknown` are always accepted.
+    pub fn allowed_iso_codes<S: Into<String>>(
+        mut self,
+        codes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.allowed_iso_codes = Some(codes.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Makes [`build`](Self::build) check the instance id against `format`.
+    pub fn instance_id_format(mut self, format: InstanceIdFormat) -> Self {
+        self.instance_id_format = format;
+        self
+    }
+
+    /// Builds the subject, running [`MyceliumSubject::validate`] so that it always displays as
+    /// a string that parses back to it.
+    pub fn build(self) -> Result<MyceliumSubject, BuildError> {
+        if let (Some(allowed), Some(GeoLocator::Locator(locator))) =
+            (&self.allowed_iso_codes, &self.geo_locator)
+        {
+            let code = locator.iso_3166_2();
+            if !allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(code))
+            {
+                return Err(BuildError::DisallowedIsoCode(code.into()));
+            }
+        }
+        if let Some(service_identifier) = &self.service_identifier {
+            self.instance_id_format
+                .validate(service_identifier.instance_id())
+                .map_err(BuildError::InvalidSubject)?;
+        }
+        if self.reject_reserved {
+            for payload_identifier in &self.payload_identifier {
+                validate_not_reserved(payload_identifier)
+                    .map_err(BuildError::InvalidPayloadIdentifier)?;
+            }
+        }
+        let payload_identifier = PayloadPath::with_max_depth(
+            self.payload_identifier,
+            self.max_payload_depth
+                .unwrap_or(PayloadPath::DEFAULT_MAX_DEPTH),
+        )
+        .map_err(BuildError::InvalidPayloadIdentifier)?;
+        let subject = MyceliumSubject {
+            environment: self
+                .environment
+                .ok_or(BuildError::MissingField("environment"))?,
+            ownership_group: self
+                .ownership_group
+                .ok_or(BuildError::MissingField("ownership_group"))?,
+            geo_locator: self
+                .geo_locator
+                .ok_or(BuildError::MissingField("geo_locator"))?,
+            service_identifier: self
+                .service_identifier
+                .ok_or(BuildError::MissingField("service_identifier"))?,
+            payload_type: self
+                .payload_type
+                .ok_or(BuildError::MissingField("payload_type"))?,
+            payload_identifier,
+        };
+        subject.validate().map_err(BuildError::InvalidSubject)?;
+        if let Some(max_bytes) = self.max_length {
+            subject
+                .validate_length(max_bytes)
+                .map_err(BuildError::InvalidSubject)?;
+        }
+        Ok(subject)
+    }
+}
+
+impl MyceliumSubject {
+    pub fn builder() -> MyceliumSubjectBuilder {
+        MyceliumSubjectBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn template() -> MyceliumSubjectBuilder {
+        MyceliumSubject::builder()
+            .environment(Environment::Production)
+            .ownership_group(OwnershipGroup::from_str("abc.xyz").unwrap())
+            .geo_locator(GeoLocator::from_str("US-CA.south.abc").unwrap())
+            .service_identifier(ServiceIdentifier::from_str("plc-gateway.1").unwrap())
+    }
+
+    #[test]
+    fn builder_build_success() {
+        let res = template()
+            .payload_type(PayloadType::Data)
+            .push_payload_identifier("system")
+            .push_payload_identifier("sub-system")
+            .push_payload_identifier("sensor")
+            .push_payload_identifier("value")
+            .build()
+            .unwrap();
+        let expected = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        assert_eq!(expected, res);
+    }
+
+    #[test]
+    fn builder_build_fail_unparsable() {
+        let res = template()
+            .ownership_group(OwnershipGroup {
+                levels: vec!["abc".to_string(), "xyz".to_string(), "US-TX".to_string()],
+            })
+            .payload_type(PayloadType::Data)
+            .build();
+        assert_eq!(
+            Err(BuildError::InvalidSubject(
+                ParseError::InvalidOwnershipGroup.at(3, 13)
+            )),
+            res
+        );
+
+        let res = (0..=PayloadPath::DEFAULT_MAX_DEPTH)
+            .fold(template().payload_type(PayloadType::Data), |builder, _| {
+                builder.push_payload_identifier("a")
+            })
+            .max_payload_depth(PayloadPath::DEFAULT_MAX_DEPTH + 1)
+            .build();
+        assert!(matches!(
+            res.unwrap_err(),
+            BuildError::InvalidSubject(error) if matches!(error.kind(), ParseError::TooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn builder_template_reuse() {
+        let base = template().payload_type(PayloadType::Data);
+        let first = base.clone().push_payload_identifier("a").build().unwrap();
+        let second = base.clone().push_payload_identifier("b").build().unwrap();
+        assert_eq!(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.a",
+            first.to_string()
+        );
+        assert_eq!(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.b",
+            second.to_string()
+        );
+    }
+
+    #[test]
+    fn builder_build_fail_missing_field() {
+        let res = template().build();
+        assert_eq!(Err(BuildError::MissingField("payload_type")), res);
+    }
+
+    #[test]
+    fn builder_build_fail_empty_payload_identifier() {
+        let res = template()
+            .payload_type(PayloadType::Data)
+            .push_payload_identifier("system")
+            .push_payload_identifier("")
+            .build();
+        assert_eq!(
+            Err(BuildError::InvalidPayloadIdentifier(ParseError::EmptyToken)),
+            res
+        );
+    }
+
+    #[test]
+    fn builder_build_fail_max_length() {
+        let base = template().payload_type(PayloadType::Data).max_length(50);
+        assert!(base.clone().push_payload_identifier("a").build().is_ok());
+        let res = base
+            .push_payload_identifier("a-much-longer-identifier")
+            .build();
+        assert_eq!(
+            Err(BuildError::InvalidSubject(ParseError::TooLong {
+                length: 72,
+                max: 50
+            })),
+            res
+        );
+    }
+
+    #[test]
+    fn builder_build_fail_max_payload_depth() {
+        let base = template()
+            .payload_type(PayloadType::Data)
+            .max_payload_depth(1);
+        assert!(base.clone().push_payload_identifier("a").build().is_ok());
+        let res = base
+            .push_payload_identifier("a")
+            .push_payload_identifier("b")
+            .build();
+        assert_eq!(
+            Err(BuildError::InvalidPayloadIdentifier(ParseError::TooDeep {
+                depth: 2,
+                max: 1
+            })),
+            res
+        );
+    }
+
+    #[test]
+    fn builder_default_environment() {
+        let res = MyceliumSubjectBuilder::default()
+            .ownership_group(OwnershipGroup::from_str("abc.xyz").unwrap())
+            .geo_locator(GeoLocator::Local)
+            .service_identifier(ServiceIdentifier::from_str("plc-gateway.1").unwrap())
+            .payload_type(PayloadType::Data)
+            .build()
+            .unwrap();
+        assert_eq!(Environment::Dev, res.environment);
+    }
+
+    #[test]
+    fn builder_reject_reserved_payload_identifiers() {
+        let base = template()
+            .payload_type(PayloadType::Data)
+            .push_payload_identifier("global");
+        assert!(base.clone().build().is_ok());
+        assert_eq!(
+            Err(BuildError::InvalidPayloadIdentifier(
+                ParseError::ReservedWord("global".to_string())
+            )),
+            base.reject_reserved_payload_identifiers().build()
+        );
+    }
+
+    #[test]
+    fn builder_from_prefix() {
+        let prefixes = [
+            "prod.abc.xyz.local.plc-gateway.1",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1",
+        ];
+        for prefix in prefixes {
+            let res = MyceliumSubjectBuilder::from_prefix(prefix)
+                .unwrap()
+                .payload_type(PayloadType::Data)
+                .push_payload_identifier("value")
+                .build()
+                .unwrap();
+            assert_eq!(format!("{prefix}.data.value"), res.to_string());
+        }
+        assert_eq!(
+            Err(BuildError::MissingField("payload_type")),
+            MyceliumSubjectBuilder::from_prefix(prefixes[0])
+                .unwrap()
+                .build()
+        );
+    }
+
+    #[test]
+    fn builder_from_prefix_fail() {
+        assert_eq!(
+            Err(ParseError::TooFewTokens {
+                expected: 6,
+                found: 5
+            }),
+            MyceliumSubjectBuilder::from_prefix("prod.abc.xyz.local.plc-gateway")
+        );
+        assert_eq!(
+            Err(ParseError::TooManyTokens {
+                expected: 6,
+                found: 7
+            }),
+            MyceliumSubjectBuilder::from_prefix("prod.abc.xyz.local.plc-gateway.1.data")
+        );
+        let res = MyceliumSubjectBuilder::from_prefix("production.abc.xyz.local.plc-gateway.1");
+        assert_eq!(Some(0), res.unwrap_err().token_index());
+    }
+
+    #[test]
+    fn builder_allowed_iso_codes() {
+        let base = template()
+            .payload_type(PayloadType::Data)
+            .allowed_iso_codes(["us-ca", "CA-QC"]);
+        assert!(base.clone().build().is_ok());
+        assert!(base.clone().geo_locator(GeoLocator::Local).build().is_ok());
+        let res = base
+            .geo_locator(GeoLocator::from_str("US-TX.south.abc").unwrap())
+            .build();
+        assert_eq!(Err(BuildError::DisallowedIsoCode("US-TX".to_string())), res);
+    }
+
+    #[test]
+    fn builder_instance_id_format() {
+        let uuid = "67e55044-10b1-426f-9247-BB680E5FE0C8";
+        let with_id = |id: &str| {
+            template()
+                .payload_type(PayloadType::Data)
+                .service_identifier(
+                    ServiceIdentifier::from_str(&format!("plc-gateway.{id}")).unwrap(),
+                )
+        };
+        for id in ["1", "abc", uuid] {
+            assert!(with_id(id).build().is_ok());
+        }
+
+        assert!(
+            with_id("42")
+                .instance_id_format(InstanceIdFormat::Numeric)
+                .build()
+                .is_ok()
+        );
+        let res = with_id("4a")
+            .instance_id_format(InstanceIdFormat::Numeric)
+            .build();
+        assert_eq!(
+            Err(BuildError::InvalidSubject(ParseError::InvalidInstanceId {
+                id: "4a".to_string(),
+                expected: InstanceIdFormat::Numeric
+            })),
+            res
+        );
+
+        assert!(
+            with_id(uuid)
+                .instance_id_format(InstanceIdFormat::Uuid)
+                .build()
+                .is_ok()
+        );
+        for id in [
+            "1",
+            "67e55044-10b1-426f-9247",
+            "67e55044-10b1-426f-9247-bb680e5fe0cg",
+        ] {
+            assert!(
+                with_id(id)
+                    .instance_id_format(InstanceIdFormat::Uuid)
+                    .build()
+                    .is_err()
+            );
+        }
+    }
+}
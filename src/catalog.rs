@@ -0,0 +1,70 @@
+use crate::{MyceliumSubject, ParseError, SUBJECT_SEPARATOR};
+use alloc::{string::String, vec::Vec};
+use core::str::FromStr;
+
+/// An invalid entry reported by [`validate_catalog`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CatalogIssue {
+    /// 1-based line number of the entry.
+    pub line: usize,
+    /// The token the error points at, when the error carries a position.
+    pub token: Option<String>,
+    pub error: ParseError,
+}
+
+/// Checks a catalog of one subject per line, returning only the invalid entries.
+///
+/// Blank lines are skipped, like in `parse_lines`. An empty result means every entry is a
+/// valid subject.
+pub fn validate_catalog(input: &str) -> Vec<CatalogIssue> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| {
+            let error = MyceliumSubject::from_str(line).err()?;
+            let token = error
+                .token_index()
+                .and_then(|token_index| line.split(SUBJECT_SEPARATOR).nth(token_index))
+                .map(String::from);
+            Some(CatalogIssue {
+                line: index + 1,
+                token,
+                error,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn validate_catalog_issues() {
+        let catalog = "prod.abc.xyz.local.plc-gateway.1.data\n\
+                       \n\
+                       production.abc.xyz.local.plc-gateway.1.data\n\
+                       dev.abc.xyz.global.plc-gateway.1.event.alarm\n\
+                       prod.abc.xyz\n";
+        let issues = validate_catalog(catalog);
+        assert_eq!(2, issues.len());
+
+        assert_eq!(3, issues[0].line);
+        assert_eq!(Some("production".to_string()), issues[0].token);
+        assert_eq!(
+            &ParseError::InvalidEnvironment("production".to_string()),
+            issues[0].error.kind()
+        );
+
+        assert_eq!(5, issues[1].line);
+        assert_eq!(None, issues[1].token);
+        assert!(matches!(
+            issues[1].error.kind(),
+            ParseError::TooFewTokens { .. }
+        ));
+
+        assert!(validate_catalog("prod.abc.xyz.local.plc-gateway.1.data\n").is_empty());
+    }
+}
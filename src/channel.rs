@@ -0,0 +1,69 @@
+use crate::{
+    Environment, GeoLocator, MyceliumSubject, OwnershipGroup, PayloadType, ServiceIdentifier,
+};
+
+/// Borrowed key over everything in a subject except its payload identifiers.
+///
+/// Subjects with the same key are published on the same channel: the same payload type from
+/// the same service instance.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct ChannelKey<'a> {
+    pub environment: Environment,
+    pub ownership_group: &'a OwnershipGroup,
+    pub geo_locator: &'a GeoLocator,
+    pub service_identifier: &'a ServiceIdentifier,
+    pub payload_type: &'a PayloadType,
+}
+
+impl MyceliumSubject {
+    pub fn channel_key(&self) -> ChannelKey<'_> {
+        ChannelKey {
+            environment: self.environment,
+            ownership_group: &self.ownership_group,
+            geo_locator: &self.geo_locator,
+            service_identifier: &self.service_identifier,
+            payload_type: &self.payload_type,
+        }
+    }
+
+    /// Whether both subjects are equal once their payload identifiers are ignored.
+    pub fn same_channel(&self, other: &MyceliumSubject) -> bool {
+        self.channel_key() == other.channel_key()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+    use std::collections::HashSet;
+
+    #[test]
+    fn same_channel_ignores_payload_identifier() {
+        let first = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.system.value")
+            .unwrap();
+        let second =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.other").unwrap();
+        let other_type =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.event.other").unwrap();
+        assert!(first.same_channel(&second));
+        assert_ne!(first, second);
+        assert!(!first.same_channel(&other_type));
+    }
+
+    #[test]
+    fn channel_key_dedup() {
+        let subjects: Vec<MyceliumSubject> = [
+            "prod.abc.xyz.local.plc-gateway.1.data.a",
+            "prod.abc.xyz.local.plc-gateway.1.data.b",
+            "prod.abc.xyz.local.plc-gateway.2.data.a",
+            "prod.abc.xyz.local.plc-gateway.1.heartbeat",
+        ]
+        .iter()
+        .map(|s| MyceliumSubject::from_str(s).unwrap())
+        .collect();
+        let channels: HashSet<ChannelKey<'_>> =
+            subjects.iter().map(MyceliumSubject::channel_key).collect();
+        assert_eq!(3, channels.len());
+    }
+}
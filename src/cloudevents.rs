@@ -0,0 +1,71 @@
+use crate::MyceliumSubject;
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use serde::{Deserialize, Serialize};
+
+/// CloudEvents context attributes derived from a [`MyceliumSubject`].
+///
+/// The mapping is part of the public contract:
+/// - `source` is the dotted subject up to and including the instance id, i.e. the
+///   environment, ownership group, geo-locator and service identifier.
+/// - `type` is `mycelium.` followed by the payload type, e.g. `mycelium.data`.
+/// - `subject` is the dotted payload identifier path, or `None` when it is empty.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct CloudEventAttrs {
+    pub source: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+}
+
+impl MyceliumSubject {
+    pub fn to_cloudevent_attrs(&self) -> CloudEventAttrs {
+        let source_len = self.token_count() - 1 - self.payload_identifier.depth();
+        let source: Vec<Cow<'_, str>> = self.tokens().take(source_len).collect();
+        CloudEventAttrs {
+            source: source.join("."),
+            event_type: alloc::format!("mycelium.{}", self.payload_type),
+            subject: (self.payload_identifier.depth() > 0)
+                .then(|| self.payload_identifier.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn cloudevent_attrs() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        assert_eq!(
+            CloudEventAttrs {
+                source: "prod.abc.xyz.US-CA.south.abc.plc-gateway.1".to_string(),
+                event_type: "mycelium.data".to_string(),
+                subject: Some("system.sub-system.sensor.value".to_string()),
+            },
+            subject.to_cloudevent_attrs()
+        );
+    }
+
+    #[test]
+    fn cloudevent_attrs_without_payload_identifier() {
+        let subject = MyceliumSubject::from_str("dev.abc.xyz.global.plc-gateway.1.event").unwrap();
+        let attrs = subject.to_cloudevent_attrs();
+        assert_eq!("dev.abc.xyz.global.plc-gateway.1", attrs.source);
+        assert_eq!("mycelium.event", attrs.event_type);
+        assert_eq!(None, attrs.subject);
+        assert_eq!(
+            r#"{"source":"dev.abc.xyz.global.plc-gateway.1","type":"mycelium.event"}"#,
+            serde_json::to_string(&attrs).unwrap()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use crate::MyceliumSubject;
+use alloc::{string::ToString, sync::Arc};
+use core::fmt::Display;
+
+/// The canonical string of a subject, formatted once and shared.
+///
+/// Cloning only bumps a reference count, so one value can be handed to many publishers
+/// without formatting the subject again.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CompactSubject(Arc<str>);
+
+impl CompactSubject {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&MyceliumSubject> for CompactSubject {
+    fn from(subject: &MyceliumSubject) -> Self {
+        CompactSubject(subject.to_string().into())
+    }
+}
+
+impl Display for CompactSubject {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn compact_subject_as_str() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        let compact = CompactSubject::from(&subject);
+        assert_eq!(subject.to_string(), compact.as_str());
+
+        let shared = compact.clone();
+        assert!(core::ptr::eq(compact.as_str(), shared.as_str()));
+    }
+}
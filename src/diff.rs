@@ -0,0 +1,131 @@
+use crate::{
+    Environment, GeoLocator, MyceliumSubject, OwnershipGroup, PayloadType, ServiceIdentifier,
+};
+use alloc::{string::String, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+/// A component that differs between two subjects.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+impl<T: PartialEq + Clone> Change<T> {
+    fn between(from: &T, to: &T) -> Option<Self> {
+        (from != to).then(|| Change {
+            from: from.clone(),
+            to: to.clone(),
+        })
+    }
+}
+
+/// How the payload identifiers differ, past the segments both subjects share.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct PayloadPathChange {
+    /// Index of the first segment that differs.
+    pub index: usize,
+    /// Segments of the old subject from `index` on.
+    pub removed: Vec<String>,
+    /// Segments of the new subject from `index` on.
+    pub added: Vec<String>,
+}
+
+/// Component-by-component difference between two subjects, see [`MyceliumSubject::diff`].
+///
+/// Unchanged components are `None` and left out when serialized.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Default)]
+#[serde(default)]
+pub struct SubjectDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Change<Environment>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ownership_group: Option<Change<OwnershipGroup>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo_locator: Option<Change<GeoLocator>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_identifier: Option<Change<ServiceIdentifier>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_type: Option<Change<PayloadType>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_identifier: Option<PayloadPathChange>,
+}
+
+impl SubjectDiff {
+    /// Whether both subjects were equal.
+    pub fn is_empty(&self) -> bool {
+        *self == SubjectDiff::default()
+    }
+}
+
+impl MyceliumSubject {
+    /// The components that differ going from `self` to `other`.
+    pub fn diff(&self, other: &MyceliumSubject) -> SubjectDiff {
+        let (from, to) = (&self.payload_identifier, &other.payload_identifier);
+        let index = from
+            .iter()
+            .zip(to.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let payload_identifier = (from != to).then(|| PayloadPathChange {
+            index,
+            removed: from[index..].to_vec(),
+            added: to[index..].to_vec(),
+        });
+        SubjectDiff {
+            environment: Change::between(&self.environment, &other.environment),
+            ownership_group: Change::between(&self.ownership_group, &other.ownership_group),
+            geo_locator: Change::between(&self.geo_locator, &other.geo_locator),
+            service_identifier: Change::between(
+                &self.service_identifier,
+                &other.service_identifier,
+            ),
+            payload_type: Change::between(&self.payload_type, &other.payload_type),
+            payload_identifier,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use core::str::FromStr;
+
+    #[test]
+    fn subject_diff_payload() {
+        let old = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        let new = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.event.system.sub-system.alarm.value",
+        )
+        .unwrap();
+        let diff = old.diff(&new);
+        assert_eq!(
+            SubjectDiff {
+                payload_type: Some(Change {
+                    from: PayloadType::Data,
+                    to: PayloadType::Event,
+                }),
+                payload_identifier: Some(PayloadPathChange {
+                    index: 2,
+                    removed: vec!["sensor".to_string(), "value".to_string()],
+                    added: vec!["alarm".to_string(), "value".to_string()],
+                }),
+                ..SubjectDiff::default()
+            },
+            diff
+        );
+        assert_eq!(
+            r#"{"payload_type":{"from":"data","to":"event"},"payload_identifier":{"index":2,"removed":["sensor","value"],"added":["alarm","value"]}}"#,
+            serde_json::to_string(&diff).unwrap()
+        );
+        assert_eq!(
+            diff,
+            serde_json::from_str(&serde_json::to_string(&diff).unwrap()).unwrap()
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+}
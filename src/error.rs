@@ -0,0 +1,85 @@
+use crate::InstanceIdFormat;
+use alloc::{boxed::Box, string::String};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq, Clone)]
+pub enum ParseError {
+    #[error("expected at least {expected} tokens, found {found}")]
+    TooFewTokens { expected: usize, found: usize },
+    #[error("expected {expected} tokens, found {found}")]
+    TooManyTokens { expected: usize, found: usize },
+    #[error("invalid environment {0:?}")]
+    InvalidEnvironment(String),
+    #[error("invalid ISO 3166-2 code {0:?}")]
+    InvalidIsoCode(String),
+    #[error("invalid ownership group format, expected at least: enterprise.op_group")]
+    InvalidOwnershipGroup,
+    #[error("invalid locator format, expected: iso.region.id")]
+    InvalidLocator,
+    #[error("invalid service identifier format, expected: name.id")]
+    InvalidServiceIdentifier,
+    #[error("instance id {id:?} is not {expected}")]
+    InvalidInstanceId {
+        id: String,
+        expected: InstanceIdFormat,
+    },
+    #[error("invalid payload type {0:?}")]
+    InvalidPayloadType(String),
+    #[error("token must not be empty")]
+    EmptyToken,
+    #[error("token {token:?} contains invalid character {character:?}")]
+    InvalidCharacter { token: String, character: char },
+    #[error("subject is {length} bytes long, maximum is {max}")]
+    TooLong { length: usize, max: usize },
+    #[error("payload identifier is {depth} segments deep, maximum is {max}")]
+    TooDeep { depth: usize, max: usize },
+    #[error("{0:?} is a reserved word")]
+    ReservedWord(String),
+    #[error("subject is not valid UTF-8: {0}")]
+    InvalidUtf8(core::str::Utf8Error),
+    #[error("invalid binary subject encoding at byte {0}")]
+    InvalidEncoding(usize),
+    #[error("multi-token wildcard '>' is only valid as the final token")]
+    MisplacedMultiWildcard,
+    #[error("{error} at token {token_index} (byte offset {byte_offset})")]
+    AtToken {
+        token_index: usize,
+        byte_offset: usize,
+        #[source]
+        error: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    pub(crate) fn at(self, token_index: usize, byte_offset: usize) -> Self {
+        ParseError::AtToken {
+            token_index,
+            byte_offset,
+            error: Box::new(self),
+        }
+    }
+
+    /// The underlying error, without any positional information.
+    pub fn kind(&self) -> &ParseError {
+        match self {
+            ParseError::AtToken { error, .. } => error.kind(),
+            _ => self,
+        }
+    }
+
+    /// Zero-based index of the offending token in the parsed subject, if known.
+    pub fn token_index(&self) -> Option<usize> {
+        match self {
+            ParseError::AtToken { token_index, .. } => Some(*token_index),
+            _ => None,
+        }
+    }
+
+    /// Byte offset in the parsed subject where the offending token starts, if known.
+    pub fn byte_offset(&self) -> Option<usize> {
+        match self {
+            ParseError::AtToken { byte_offset, .. } => Some(*byte_offset),
+            _ => None,
+        }
+    }
+}
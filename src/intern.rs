@@ -0,0 +1,163 @@
+use crate::{
+    Environment, GeoLocator, MyceliumSubject, OwnershipGroup, ParseError, PayloadPath, PayloadType,
+    ServiceIdentifier,
+};
+use alloc::{collections::BTreeSet, sync::Arc};
+use core::{fmt::Display, str::FromStr};
+
+/// A subject whose ownership group, geo-locator and service identifier are shared.
+///
+/// Built by a [`SubjectInterner`]: subjects parsed by the same interner point at the same
+/// allocation for equal components, so cloning or storing many of them is cheap.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct InternedSubject {
+    environment: Environment,
+    ownership_group: Arc<OwnershipGroup>,
+    geo_locator: Arc<GeoLocator>,
+    service_identifier: Arc<ServiceIdentifier>,
+    payload_type: PayloadType,
+    payload_identifier: PayloadPath,
+}
+
+impl InternedSubject {
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    pub fn ownership_group(&self) -> &Arc<OwnershipGroup> {
+        &self.ownership_group
+    }
+
+    pub fn geo_locator(&self) -> &Arc<GeoLocator> {
+        &self.geo_locator
+    }
+
+    pub fn service_identifier(&self) -> &Arc<ServiceIdentifier> {
+        &self.service_identifier
+    }
+
+    pub fn payload_type(&self) -> &PayloadType {
+        &self.payload_type
+    }
+
+    pub fn payload_identifier(&self) -> &PayloadPath {
+        &self.payload_identifier
+    }
+
+    /// Copies the shared components into a standalone [`MyceliumSubject`].
+    pub fn to_subject(&self) -> MyceliumSubject {
+        MyceliumSubject {
+            environment: self.environment,
+            ownership_group: OwnershipGroup::clone(&self.ownership_group),
+            geo_locator: GeoLocator::clone(&self.geo_locator),
+            service_identifier: ServiceIdentifier::clone(&self.service_identifier),
+            payload_type: self.payload_type.clone(),
+            payload_identifier: self.payload_identifier.clone(),
+        }
+    }
+}
+
+impl Display for InternedSubject {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}", self.environment, self.ownership_group)?;
+        if *self.geo_locator != GeoLocator::Omitted {
+            write!(f, ".{}", self.geo_locator)?;
+        }
+        write!(f, ".{}.{}", self.service_identifier, self.payload_type)?;
+        for part in &self.payload_identifier {
+            write!(f, ".{part}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Deduplicates the components that recur across subjects, see [`InternedSubject`].
+///
+/// Interned values are kept until the interner is dropped.
+#[derive(Debug, Default)]
+pub struct SubjectInterner {
+    ownership_groups: BTreeSet<Arc<OwnershipGroup>>,
+    geo_locators: BTreeSet<Arc<GeoLocator>>,
+    service_identifiers: BTreeSet<Arc<ServiceIdentifier>>,
+}
+
+/// Returns the shared copy of `value`, adding it to `set` first if needed.
+fn intern<T: Ord>(set: &mut BTreeSet<Arc<T>>, value: T) -> Arc<T> {
+    match set.get(&value) {
+        Some(shared) => shared.clone(),
+        None => {
+            let shared = Arc::new(value);
+            set.insert(shared.clone());
+            shared
+        }
+    }
+}
+
+impl SubjectInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses `s` like [`MyceliumSubject::from_str`] and interns its components.
+    pub fn parse(&mut self, s: &str) -> Result<InternedSubject, ParseError> {
+        Ok(self.intern(MyceliumSubject::from_str(s)?))
+    }
+
+    /// Interns the components of an already parsed subject.
+    pub fn intern(&mut self, subject: MyceliumSubject) -> InternedSubject {
+        InternedSubject {
+            environment: subject.environment,
+            ownership_group: intern(&mut self.ownership_groups, subject.ownership_group),
+            geo_locator: intern(&mut self.geo_locators, subject.geo_locator),
+            service_identifier: intern(&mut self.service_identifiers, subject.service_identifier),
+            payload_type: subject.payload_type,
+            payload_identifier: subject.payload_identifier,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn interner_shares_components() {
+        let mut interner = SubjectInterner::new();
+        let first = interner
+            .parse("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor")
+            .unwrap();
+        let second = interner
+            .parse("prod.abc.xyz.US-CA.south.abc.plc-gateway.2.event.alarm")
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            first.ownership_group(),
+            second.ownership_group()
+        ));
+        assert!(Arc::ptr_eq(first.geo_locator(), second.geo_locator()));
+        assert!(!Arc::ptr_eq(
+            first.service_identifier(),
+            second.service_identifier()
+        ));
+
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        assert_eq!(subject, first.to_subject());
+        assert_eq!(subject.to_string(), first.to_string());
+        assert!(interner.parse("prod.abc").is_err());
+    }
+
+    #[test]
+    fn interner_legacy_subject() {
+        let mut interner = SubjectInterner::new();
+        let subject =
+            MyceliumSubject::from_str_no_geo("prod.abc.xyz.plc-gateway.1.data.temp").unwrap();
+        let interned = interner.intern(subject.clone());
+        assert_eq!(GeoLocator::Omitted, **interned.geo_locator());
+        assert_eq!("prod.abc.xyz.plc-gateway.1.data.temp", interned.to_string());
+        assert_eq!(subject.to_string(), interned.to_string());
+        assert_eq!(subject, interned.to_subject());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::MyceliumSubject;
+use alloc::string::{String, ToString};
+use thiserror::Error;
+
+/// Kafka rejects topic names longer than this.
+const MAX_TOPIC_LENGTH: usize = 249;
+
+#[derive(Debug, Error, PartialEq, Eq, Clone)]
+pub enum TopicError {
+    #[error("token {token:?} contains character {character:?}, not allowed in Kafka topics")]
+    InvalidCharacter { token: String, character: char },
+    #[error("separator {0:?} is not allowed in Kafka topics")]
+    InvalidSeparator(char),
+    #[error("topic is {length} bytes long, maximum is {max}")]
+    TooLong { length: usize, max: usize },
+}
+
+fn is_kafka_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')
+}
+
+impl MyceliumSubject {
+    /// The subject as a Kafka topic name, with `.` between tokens.
+    pub fn to_kafka_topic(&self) -> Result<String, TopicError> {
+        self.to_kafka_topic_with_separator('.')
+    }
+
+    /// The subject as a Kafka topic name, with `separator` between tokens.
+    ///
+    /// Kafka topics only allow `[a-zA-Z0-9._-]` and at most 249 bytes, so this fails when a
+    /// token or the separator falls outside that set, or when the topic gets too long.
+    pub fn to_kafka_topic_with_separator(&self, separator: char) -> Result<String, TopicError> {
+        if !is_kafka_char(separator) {
+            return Err(TopicError::InvalidSeparator(separator));
+        }
+        let mut topic = String::new();
+        for (index, token) in self.tokens().enumerate() {
+            if let Some(character) = token.chars().find(|&c| !is_kafka_char(c)) {
+                return Err(TopicError::InvalidCharacter {
+                    token: token.to_string(),
+                    character,
+                });
+            }
+            if index > 0 {
+                topic.push(separator);
+            }
+            topic.push_str(&token);
+        }
+        if topic.len() > MAX_TOPIC_LENGTH {
+            return Err(TopicError::TooLong {
+                length: topic.len(),
+                max: MAX_TOPIC_LENGTH,
+            });
+        }
+        Ok(topic)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn kafka_topic_success() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(subject_string, subject.to_kafka_topic().unwrap());
+        assert_eq!(
+            subject_string.replace('.', "_"),
+            subject.to_kafka_topic_with_separator('_').unwrap()
+        );
+        assert_eq!(
+            Err(TopicError::InvalidSeparator('/')),
+            subject.to_kafka_topic_with_separator('/')
+        );
+    }
+
+    #[test]
+    fn kafka_topic_fail_invalid_character() {
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc:gateway.1.data").unwrap();
+        assert_eq!(
+            Err(TopicError::InvalidCharacter {
+                token: "plc:gateway".to_string(),
+                character: ':'
+            }),
+            subject.to_kafka_topic()
+        );
+    }
+
+    #[test]
+    fn kafka_topic_fail_too_long() {
+        let base = "prod.abc.xyz.local.plc-gateway.1.data.";
+        let at_limit = format!("{base}{}", "a".repeat(MAX_TOPIC_LENGTH - base.len()));
+        let subject = MyceliumSubject::from_str(&at_limit).unwrap();
+        assert_eq!(at_limit, subject.to_kafka_topic().unwrap());
+
+        let too_long = format!("{at_limit}a");
+        let subject = MyceliumSubject::from_str(&too_long).unwrap();
+        assert_eq!(
+            Err(TopicError::TooLong {
+                length: MAX_TOPIC_LENGTH + 1,
+                max: MAX_TOPIC_LENGTH
+            }),
+            subject.to_kafka_topic()
+        );
+    }
+}
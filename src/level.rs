@@ -0,0 +1,168 @@
+use crate::{
+    Environment, MyceliumSubject, PayloadPath, PayloadType, SUBJECT_SEPARATOR, ServiceIdentifier,
+    SubjectPattern,
+};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+/// A level of the subject hierarchy, from the broadest to the most specific.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Copy)]
+pub enum SubjectLevel {
+    Environment,
+    Ownership,
+    Geo,
+    Service,
+    PayloadType,
+    /// The payload type followed by at most this many payload identifiers.
+    Payload(usize),
+}
+
+impl MyceliumSubject {
+    fn level_token_count(&self, level: SubjectLevel) -> usize {
+        let ownership = Environment::TOKENS + self.ownership_group.levels().len();
+        let geo = ownership + self.geo_locator.token_count();
+        let service = geo + ServiceIdentifier::TOKENS;
+        let payload_type = service + PayloadType::TOKENS;
+        match level {
+            SubjectLevel::Environment => Environment::TOKENS,
+            SubjectLevel::Ownership => ownership,
+            SubjectLevel::Geo => geo,
+            SubjectLevel::Service => service,
+            SubjectLevel::PayloadType => payload_type,
+            SubjectLevel::Payload(depth) => {
+                payload_type + depth.min(self.payload_identifier.depth())
+            }
+        }
+    }
+
+    /// The dotted tokens of this subject up to and including `level`.
+    pub fn prefix(&self, level: SubjectLevel) -> String {
+        let tokens: Vec<Cow<'_, str>> = self.tokens().take(self.level_token_count(level)).collect();
+        tokens.join(&SUBJECT_SEPARATOR.encode_utf8(&mut [0; 4])[..])
+    }
+
+    /// This subject with only the tokens up to `level`.
+    ///
+    /// A subject always ends with its payload type, so only [`SubjectLevel::PayloadType`] and
+    /// [`SubjectLevel::Payload`] can be truncated to; broader levels return `None`, use
+    /// [`Self::prefix`] for those.
+    pub fn truncate_to(&self, level: SubjectLevel) -> Option<MyceliumSubject> {
+        let depth = match level {
+            SubjectLevel::PayloadType => 0,
+            SubjectLevel::Payload(depth) => depth.min(self.payload_identifier.depth()),
+            _ => return None,
+        };
+        let mut subject = self.clone();
+        subject.payload_identifier = PayloadPath(self.payload_identifier[..depth].to_vec());
+        Some(subject)
+    }
+
+    /// Every pattern a subscriber could have registered to receive this subject, broadest
+    /// first.
+    ///
+    /// There is one `prefix.>` pattern per [`SubjectLevel`] shorter than the subject, from
+    /// [`SubjectLevel::Environment`] down to each payload path prefix, followed by the subject
+    /// itself as a literal pattern. Wildcards in the middle of a pattern are not generated.
+    pub fn ancestor_patterns(&self) -> Vec<SubjectPattern> {
+        let token_count = self.token_count();
+        let levels = [
+            SubjectLevel::Environment,
+            SubjectLevel::Ownership,
+            SubjectLevel::Geo,
+            SubjectLevel::Service,
+            SubjectLevel::PayloadType,
+        ]
+        .into_iter()
+        .chain((1..self.payload_identifier.depth()).map(SubjectLevel::Payload));
+        let mut patterns: Vec<SubjectPattern> = levels
+            .map(|level| self.level_token_count(level))
+            .filter(|&prefix_len| prefix_len < token_count)
+            .map(|prefix_len| self.prefix_wildcard(prefix_len))
+            .collect();
+        patterns.push(SubjectPattern::from(self));
+        patterns
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    const SUBJECT: &str =
+        "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+
+    #[test]
+    fn subject_prefix_each_level() {
+        let subject = MyceliumSubject::from_str(SUBJECT).unwrap();
+        let cases = [
+            (SubjectLevel::Environment, "prod"),
+            (SubjectLevel::Ownership, "prod.abc.xyz"),
+            (SubjectLevel::Geo, "prod.abc.xyz.US-CA.south.abc"),
+            (
+                SubjectLevel::Service,
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1",
+            ),
+            (
+                SubjectLevel::PayloadType,
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+            ),
+            (
+                SubjectLevel::Payload(2),
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system",
+            ),
+            (SubjectLevel::Payload(10), SUBJECT),
+        ];
+        for (level, prefix) in cases {
+            assert_eq!(prefix, subject.prefix(level));
+        }
+    }
+
+    #[test]
+    fn subject_ancestor_patterns() {
+        let subject = MyceliumSubject::from_str(SUBJECT).unwrap();
+        let patterns = subject.ancestor_patterns();
+        let expected = [
+            "prod.>",
+            "prod.abc.xyz.>",
+            "prod.abc.xyz.US-CA.south.abc.>",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.>",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.>",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.>",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.>",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.>",
+            SUBJECT,
+        ];
+        assert_eq!(
+            expected.to_vec(),
+            patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()
+        );
+        assert!(patterns.iter().all(|pattern| subject.matches(pattern)));
+
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert_eq!(5, subject.ancestor_patterns().len());
+    }
+
+    #[test]
+    fn subject_truncate_to() {
+        let subject = MyceliumSubject::from_str(SUBJECT).unwrap();
+        for level in [
+            SubjectLevel::Environment,
+            SubjectLevel::Ownership,
+            SubjectLevel::Geo,
+            SubjectLevel::Service,
+        ] {
+            assert_eq!(None, subject.truncate_to(level));
+        }
+        for level in [
+            SubjectLevel::PayloadType,
+            SubjectLevel::Payload(0),
+            SubjectLevel::Payload(1),
+            SubjectLevel::Payload(4),
+            SubjectLevel::Payload(5),
+        ] {
+            let res = subject.truncate_to(level).unwrap();
+            assert_eq!(subject.prefix(level), res.to_string());
+            assert_eq!(Ok(()), res.validate());
+        }
+    }
+}
@@ -1,273 +1,1860 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(unused)]
+extern crate alloc;
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cmp::Ordering, fmt::Display, ops::Bound, str::FromStr};
+#[cfg(feature = "strict-iso")]
 use rust_iso3166::iso3166_2;
-use serde::{Deserialize, Serialize};
-use std::{fmt::Display, str::FromStr};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+
+mod amqp;
+#[cfg(feature = "proptest")]
+mod arbitrary;
+mod binary;
+mod builder;
+mod catalog;
+mod channel;
+mod cloudevents;
+mod compact;
+mod diff;
+mod error;
+mod intern;
+mod kafka;
+mod level;
+#[cfg(feature = "std")]
+mod lines;
+mod macros;
+mod mask;
+mod mqtt;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod pattern;
+mod prometheus;
+mod redact;
+pub mod serde_string;
+pub mod serde_string_vec;
+mod subject_ref;
+#[cfg(feature = "testutil")]
+pub mod testutil;
+mod trie;
+
+pub use builder::{BuildError, MyceliumSubjectBuilder};
+pub use catalog::{CatalogIssue, validate_catalog};
+pub use channel::ChannelKey;
+pub use cloudevents::CloudEventAttrs;
+pub use compact::CompactSubject;
+pub use diff::{Change, PayloadPathChange, SubjectDiff};
+pub use error::ParseError;
+pub use intern::{InternedSubject, SubjectInterner};
+pub use kafka::TopicError;
+pub use level::SubjectLevel;
+#[cfg(feature = "std")]
+pub use lines::parse_lines;
+#[cfg(feature = "rayon")]
+pub use lines::parse_many_parallel;
+pub use mask::ComponentMask;
+pub use pattern::{GeoArity, LocatorPattern, SubjectPattern, Token};
+pub use redact::RedactOptions;
+pub use subject_ref::{GeoLocatorRef, MyceliumSubjectRef};
+pub use trie::SubscriptionTrie;
+
+#[cfg(feature = "strict-iso")]
+pub use rust_iso3166::iso3166_2::Subdivision;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+/// Serializes as its subject token, e.g. `"prod"`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
 pub enum Environment {
     Production,
     Staging,
+    #[default]
     Dev,
+    Test,
+    Qa,
 }
 
-impl Display for Environment {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Separator between the tokens of a subject.
+pub const SUBJECT_SEPARATOR: char = '.';
+
+impl Environment {
+    /// Number of subject tokens an environment takes.
+    pub const TOKENS: usize = 1;
+
+    const ALL: [Environment; 5] = [
+        Environment::Production,
+        Environment::Staging,
+        Environment::Dev,
+        Environment::Test,
+        Environment::Qa,
+    ];
+
+    /// Parses the canonical lowercase form only, unlike the case-insensitive `from_str`.
+    pub fn from_str_exact(s: &str) -> Result<Self, ParseError> {
+        Self::find(s, |name| name == s)
+    }
+
+    fn find(s: &str, matches: impl Fn(&str) -> bool) -> Result<Self, ParseError> {
+        if s.is_empty() {
+            return Err(ParseError::EmptyToken);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|environment| matches(environment.as_str()))
+            .ok_or_else(|| ParseError::InvalidEnvironment(s.to_string()))
+    }
+
+    pub fn as_str(&self) -> &'static str {
         match self {
-            Environment::Production => write!(f, "prod"),
-            Environment::Staging => write!(f, "staging"),
-            Environment::Dev => write!(f, "dev"),
+            Environment::Production => "prod",
+            Environment::Staging => "staging",
+            Environment::Dev => "dev",
+            Environment::Test => "test",
+            Environment::Qa => "qa",
         }
     }
 }
 
+impl PartialOrd for Environment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Environment {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 impl FromStr for Environment {
-    type Err = &'static str;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "prod" => Ok(Environment::Production),
-            "staging" => Ok(Environment::Staging),
-            "dev" => Ok(Environment::Dev),
-            _ => Err("Invalid Environment string"),
-        }
+        Self::find(s, |name| name.eq_ignore_ascii_case(s))
+    }
+}
+
+impl Serialize for Environment {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Environment {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Environment::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Rejects payload identifier segments that read like a geo-locator or an environment.
+pub(crate) fn validate_not_reserved(segment: &str) -> Result<(), ParseError> {
+    let reserved = ["local", "global", "unknown"]
+        .into_iter()
+        .chain(Environment::ALL.iter().map(Environment::as_str))
+        .any(|word| word.eq_ignore_ascii_case(segment));
+    if reserved {
+        Err(ParseError::ReservedWord(segment.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that `token` is non-empty and free of whitespace, control characters and the NATS
+/// reserved characters `.`, `*` and `>`.
+pub(crate) fn validate_token(token: &str) -> Result<(), ParseError> {
+    if token.is_empty() {
+        return Err(ParseError::EmptyToken);
+    }
+    match token
+        .chars()
+        .find(|c| c.is_whitespace() || c.is_control() || matches!(c, '.' | '*' | '>'))
+    {
+        Some(character) => Err(ParseError::InvalidCharacter {
+            token: token.to_string(),
+            character,
+        }),
+        None => Ok(()),
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+/// The ownership levels of a subject, from the enterprise down to the op group.
+///
+/// A group has at least two levels (`enterprise.op_group`), and any number of intermediate
+/// levels may sit between them, as in `enterprise.division.op_group`.
+///
+/// Deserialization goes through [`OwnershipGroup::new`], and also accepts the
+/// `{"enterprise": .., "op_group": ..}` object written before groups could have more than two
+/// levels.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+#[serde(try_from = "OwnershipGroupRepr")]
 pub struct OwnershipGroup {
-    enterprise: String,
-    op_group: String,
+    levels: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OwnershipGroupRepr {
+    Levels {
+        levels: Vec<String>,
+    },
+    TwoLevels {
+        enterprise: String,
+        op_group: String,
+    },
+}
+
+impl TryFrom<OwnershipGroupRepr> for OwnershipGroup {
+    type Error = ParseError;
+    fn try_from(repr: OwnershipGroupRepr) -> Result<Self, Self::Error> {
+        match repr {
+            OwnershipGroupRepr::Levels { levels } => OwnershipGroup::new(levels),
+            OwnershipGroupRepr::TwoLevels {
+                enterprise,
+                op_group,
+            } => OwnershipGroup::from_parts(enterprise, op_group),
+        }
+    }
+}
+
+impl OwnershipGroup {
+    /// Fewest subject tokens an ownership group takes, the enterprise and the op group.
+    pub const MIN_TOKENS: usize = 2;
+
+    pub fn new(levels: Vec<String>) -> Result<Self, ParseError> {
+        if levels.len() < Self::MIN_TOKENS {
+            return Err(ParseError::InvalidOwnershipGroup);
+        }
+        for level in &levels {
+            validate_token(level)?;
+        }
+        Ok(OwnershipGroup { levels })
+    }
+
+    /// A two-level group, `enterprise.op_group`.
+    pub fn from_parts(
+        enterprise: impl Into<String>,
+        op_group: impl Into<String>,
+    ) -> Result<Self, ParseError> {
+        OwnershipGroup::new(alloc::vec![enterprise.into(), op_group.into()])
+    }
+
+    /// All levels, starting with the enterprise and ending with the op group.
+    pub fn levels(&self) -> &[String] {
+        &self.levels
+    }
+
+    pub fn enterprise(&self) -> &str {
+        &self.levels[0]
+    }
+
+    pub fn op_group(&self) -> &str {
+        &self.levels[self.levels.len() - 1]
+    }
+
+    pub fn set_enterprise(&mut self, enterprise: impl Into<String>) -> Result<(), ParseError> {
+        let enterprise = enterprise.into();
+        validate_token(&enterprise)?;
+        self.levels[0] = enterprise;
+        Ok(())
+    }
+
+    pub fn set_op_group(&mut self, op_group: impl Into<String>) -> Result<(), ParseError> {
+        let op_group = op_group.into();
+        validate_token(&op_group)?;
+        let last = self.levels.len() - 1;
+        self.levels[last] = op_group;
+        Ok(())
+    }
 }
 
 impl Display for OwnershipGroup {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}.{}", self.enterprise, self.op_group)
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.levels[0])?;
+        for level in &self.levels[1..] {
+            write!(f, ".{level}")?;
+        }
+        Ok(())
     }
 }
 
 impl FromStr for OwnershipGroup {
-    type Err = &'static str;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() == 2 {
-            Ok(OwnershipGroup {
-                enterprise: parts[0].to_string(),
-                op_group: parts[1].to_string(),
-            })
+        OwnershipGroup::new(s.split('.').map(str::to_string).collect())
+    }
+}
+
+/// Checks an ISO 3166-2 subdivision code, ignoring ASCII case.
+///
+/// With the `strict-iso` feature (the default) the code must exist in the ISO 3166-2 table.
+/// Without it, any `country-subdivision` pair of non-empty parts is accepted. This skips the
+/// lookup cost but lets typos and non-existent regions through, so it should only be disabled
+/// when codes are already validated upstream.
+pub(crate) fn validate_iso_code(code: &str) -> Result<(), ParseError> {
+    validate_iso_code_shape(code)?;
+    #[cfg(feature = "strict-iso")]
+    {
+        let known = if code.bytes().any(|b| b.is_ascii_lowercase()) {
+            iso3166_2::from_code(&code.to_ascii_uppercase()).is_some()
         } else {
-            Err("Invalid OwnershipGroup string format. Expected: enterprise.op_group")
+            iso3166_2::from_code(code).is_some()
+        };
+        if !known {
+            return Err(ParseError::InvalidIsoCode(code.to_string()));
         }
     }
+    Ok(())
+}
+
+/// Checks that `code` is a `country-subdivision` pair of non-empty parts, without looking it
+/// up.
+fn validate_iso_code_shape(code: &str) -> Result<(), ParseError> {
+    validate_token(code)?;
+    match code.split_once('-') {
+        Some((country, subdivision)) if !country.is_empty() && !subdivision.is_empty() => Ok(()),
+        _ => Err(ParseError::InvalidIsoCode(code.to_string())),
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+/// A full geo-locator: ISO 3166-2 code, region and identifier.
+///
+/// The region is one or more segments, as in `US-CA.south.abc` or
+/// `US-CA.north.bay-area.abc`. See [`MyceliumSubject`] for how a subject tells the last
+/// region segment from the identifier and the service name that follow.
+///
+/// Every constructor, including deserialization, stores the ISO code in uppercase, so
+/// locators compare equal whatever the case of the code they were built from. Deserialization
+/// goes through [`Locator::with_region_segments`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+#[serde(try_from = "LocatorRepr")]
 pub struct Locator {
     iso_3166_2: String,
-    op_region: String,
+    op_region: Vec<String>,
     op_identifier: String,
 }
 
-impl Display for Locator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}.{}.{}",
-            self.iso_3166_2, self.op_region, self.op_identifier
+#[derive(Deserialize)]
+struct LocatorRepr {
+    iso_3166_2: String,
+    #[serde(deserialize_with = "deserialize_region")]
+    op_region: Vec<String>,
+    op_identifier: String,
+}
+
+impl TryFrom<LocatorRepr> for Locator {
+    type Error = ParseError;
+    fn try_from(repr: LocatorRepr) -> Result<Self, Self::Error> {
+        Locator::with_region_segments(&repr.iso_3166_2, repr.op_region, &repr.op_identifier)
+    }
+}
+
+impl Locator {
+    /// Fewest subject tokens a locator takes: ISO code, a single region segment and
+    /// identifier.
+    pub const MIN_TOKENS: usize = 3;
+
+    /// Validates each part like a parsed locator and uppercases the ISO code.
+    pub fn new(iso_3166_2: &str, op_region: &str, op_identifier: &str) -> Result<Self, ParseError> {
+        Self::with_region_segments(
+            iso_3166_2,
+            alloc::vec![op_region.to_string()],
+            op_identifier,
         )
     }
+
+    /// Like [`Locator::new`], with a region made of several segments.
+    pub fn with_region_segments(
+        iso_3166_2: &str,
+        op_region: Vec<String>,
+        op_identifier: &str,
+    ) -> Result<Self, ParseError> {
+        validate_iso_code(iso_3166_2)?;
+        Self::with_trusted_iso_code(iso_3166_2, op_region, op_identifier)
+    }
+
+    /// Like [`Locator::with_region_segments`], but only checks the shape of the ISO code
+    /// instead of looking it up, for codes that were validated when first parsed.
+    pub(crate) fn with_trusted_iso_code(
+        iso_3166_2: &str,
+        op_region: Vec<String>,
+        op_identifier: &str,
+    ) -> Result<Self, ParseError> {
+        validate_iso_code_shape(iso_3166_2)?;
+        validate_region(&op_region)?;
+        validate_token(op_identifier)?;
+        Ok(Locator {
+            iso_3166_2: iso_3166_2.to_ascii_uppercase(),
+            op_region,
+            op_identifier: op_identifier.to_string(),
+        })
+    }
+
+    /// Number of subject tokens this locator takes.
+    pub fn token_count(&self) -> usize {
+        self.op_region.len() + 2
+    }
+
+    pub fn iso_3166_2(&self) -> &str {
+        &self.iso_3166_2
+    }
+
+    /// The region segments, from the broadest one down.
+    pub fn op_region(&self) -> &[String] {
+        &self.op_region
+    }
+
+    pub fn op_identifier(&self) -> &str {
+        &self.op_identifier
+    }
+
+    /// Resolves the stored code in the ISO 3166-2 table.
+    #[cfg(feature = "strict-iso")]
+    pub fn subdivision(&self) -> Option<iso3166_2::Subdivision> {
+        iso3166_2::from_code(&self.iso_3166_2)
+    }
+
+    /// ISO 3166-1 alpha-2 code of the country the subdivision belongs to, e.g. `US`.
+    #[cfg(feature = "strict-iso")]
+    pub fn country_code(&self) -> Option<&'static str> {
+        self.subdivision()
+            .map(|subdivision| subdivision.country_code)
+    }
+
+    /// Name of the subdivision, e.g. `California`.
+    #[cfg(feature = "strict-iso")]
+    pub fn subdivision_name(&self) -> Option<&'static str> {
+        self.subdivision().map(|subdivision| subdivision.name)
+    }
+
+    pub fn set_iso_3166_2(&mut self, iso_3166_2: impl Into<String>) -> Result<(), ParseError> {
+        let iso_3166_2 = iso_3166_2.into();
+        validate_iso_code(&iso_3166_2)?;
+        self.iso_3166_2 = iso_3166_2.to_ascii_uppercase();
+        Ok(())
+    }
+
+    /// Replaces the region with a single segment.
+    pub fn set_op_region(&mut self, op_region: impl Into<String>) -> Result<(), ParseError> {
+        self.set_op_region_segments(alloc::vec![op_region.into()])
+    }
+
+    pub fn set_op_region_segments(&mut self, op_region: Vec<String>) -> Result<(), ParseError> {
+        validate_region(&op_region)?;
+        self.op_region = op_region;
+        Ok(())
+    }
+
+    pub fn set_op_identifier(
+        &mut self,
+        op_identifier: impl Into<String>,
+    ) -> Result<(), ParseError> {
+        let op_identifier = op_identifier.into();
+        validate_token(&op_identifier)?;
+        self.op_identifier = op_identifier;
+        Ok(())
+    }
+}
+
+/// Checks that a region has at least one segment and that each is a valid token.
+fn validate_region(op_region: &[String]) -> Result<(), ParseError> {
+    if op_region.is_empty() {
+        return Err(ParseError::InvalidLocator);
+    }
+    op_region
+        .iter()
+        .try_for_each(|segment| validate_token(segment))
+}
+
+/// Reads the region as a list of segments, or as the single string it used to be.
+fn deserialize_region<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Region {
+        Single(String),
+        Segments(Vec<String>),
+    }
+    Ok(match Region::deserialize(deserializer)? {
+        Region::Single(segment) => alloc::vec![segment],
+        Region::Segments(segments) => segments,
+    })
+}
+
+impl Display for Locator {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.iso_3166_2)?;
+        for segment in &self.op_region {
+            write!(f, ".{segment}")?;
+        }
+        write!(f, ".{}", self.op_identifier)
+    }
+}
+
+/// Splits a dotted locator into its ISO code, its dotted region and its identifier, without
+/// collecting the tokens.
+pub(crate) fn split_locator(s: &str) -> Option<(&str, &str, &str)> {
+    let (iso3166_2_code, rest) = s.split_once('.')?;
+    let (op_region, op_identifier) = rest.rsplit_once('.')?;
+    Some((iso3166_2_code, op_region, op_identifier))
 }
 
 impl FromStr for Locator {
-    type Err = &'static str;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() == 3 {
-            let iso3166_2_code = parts[0];
-            if iso3166_2::from_code(iso3166_2_code).is_none() {
-                Err("Invalid ISO 3166-2 code")
-            } else {
-                Ok(Locator {
-                    iso_3166_2: iso3166_2_code.to_string(),
-                    op_region: parts[1].to_string(),
-                    op_identifier: parts[2].to_string(),
-                })
-            }
-        } else {
-            Err("Invalid GlobalLocator string format. Expected: iso.region.id")
-        }
+        let (iso3166_2_code, op_region, op_identifier) =
+            split_locator(s).ok_or(ParseError::InvalidLocator)?;
+        Locator::with_region_segments(
+            iso3166_2_code,
+            op_region.split('.').map(str::to_string).collect(),
+            op_identifier,
+        )
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+/// The coarse scope of a [`GeoLocator`], for callers that don't need the locator itself.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GeoScope {
+    Local,
+    Global,
+    /// A [`GeoLocator::Locator`] or [`GeoLocator::Unknown`].
+    Regional,
+    /// [`GeoLocator::Omitted`].
+    Omitted,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 pub enum GeoLocator {
     Local,
     Global,
+    /// Regional, but the ISO subdivision is not resolved yet. Its token is `unknown`.
+    Unknown,
     Locator(Locator),
+    /// No geo-locator at all, as in legacy subjects parsed with
+    /// [`MyceliumSubject::from_str_no_geo`]. It takes no tokens and displays as nothing.
+    Omitted,
+}
+
+impl GeoLocator {
+    /// A full locator, see [`Locator::new`].
+    pub fn locator(
+        iso_3166_2: &str,
+        op_region: &str,
+        op_identifier: &str,
+    ) -> Result<Self, ParseError> {
+        Locator::new(iso_3166_2, op_region, op_identifier).map(GeoLocator::Locator)
+    }
+
+    pub fn scope(&self) -> GeoScope {
+        match self {
+            GeoLocator::Local => GeoScope::Local,
+            GeoLocator::Global => GeoScope::Global,
+            GeoLocator::Unknown | GeoLocator::Locator(_) => GeoScope::Regional,
+            GeoLocator::Omitted => GeoScope::Omitted,
+        }
+    }
+
+    /// Number of subject tokens this geo-locator serializes to.
+    pub fn token_count(&self) -> usize {
+        match self {
+            GeoLocator::Local | GeoLocator::Global | GeoLocator::Unknown => 1,
+            GeoLocator::Locator(locator) => locator.token_count(),
+            GeoLocator::Omitted => 0,
+        }
+    }
+
+    fn first_token(&self) -> Option<&str> {
+        match self {
+            GeoLocator::Local => Some("local"),
+            GeoLocator::Global => Some("global"),
+            GeoLocator::Unknown => Some("unknown"),
+            GeoLocator::Locator(locator) => Some(&locator.iso_3166_2),
+            GeoLocator::Omitted => None,
+        }
+    }
+}
+
+impl PartialOrd for GeoLocator {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GeoLocator {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (GeoLocator::Locator(a), GeoLocator::Locator(b)) => a.cmp(b),
+            _ => self.first_token().cmp(&other.first_token()),
+        }
+    }
 }
 
 impl Display for GeoLocator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             GeoLocator::Local => write!(f, "local"),
             GeoLocator::Global => write!(f, "global"),
+            GeoLocator::Unknown => write!(f, "unknown"),
             GeoLocator::Locator(g) => write!(f, "{g}"),
+            GeoLocator::Omitted => Ok(()),
         }
     }
 }
 
 impl FromStr for GeoLocator {
-    type Err = &'static str;
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Self::Local),
+            "global" => Ok(Self::Global),
+            "unknown" => Ok(Self::Unknown),
+            _ => Ok(Self::Locator(Locator::from_str(s)?)),
+        }
+    }
+}
+
+/// Instance part of a [`ServiceIdentifier`].
+///
+/// Instance ids are usually numeric, but any valid token is accepted and round-trips as is.
+/// Deserialization goes through [`InstanceId::from_str`].
+#[derive(Debug, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+#[serde(try_from = "String")]
+pub struct InstanceId(String);
+
+impl TryFrom<String> for InstanceId {
+    type Error = ParseError;
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        validate_token(&id)?;
+        Ok(InstanceId(id))
+    }
+}
+
+impl Serialize for InstanceId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl InstanceId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The instance id as a number, if it is one.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.0.parse().ok()
+    }
+}
+
+impl Display for InstanceId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for InstanceId {
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() == 1 {
-            Ok(Self::Local)
+        validate_token(s)?;
+        Ok(InstanceId(s.to_string()))
+    }
+}
+
+/// Format an [`InstanceId`] is expected to follow, checked with [`InstanceIdFormat::validate`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum InstanceIdFormat {
+    /// Any valid token.
+    #[default]
+    Any,
+    /// ASCII digits only, like `1` or `042`.
+    Numeric,
+    /// A hyphenated UUID, like `67e55044-10b1-426f-9247-bb680e5fe0c8`, in either case.
+    Uuid,
+}
+
+impl InstanceIdFormat {
+    pub fn validate(self, id: &str) -> Result<(), ParseError> {
+        let valid = match self {
+            InstanceIdFormat::Any => true,
+            InstanceIdFormat::Numeric => id.bytes().all(|b| b.is_ascii_digit()),
+            InstanceIdFormat::Uuid => {
+                let groups = [8, 4, 4, 4, 12];
+                id.split('-').count() == groups.len()
+                    && id.split('-').zip(groups).all(|(group, len)| {
+                        group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit())
+                    })
+            }
+        };
+        if valid {
+            Ok(())
         } else {
-            Ok(Self::Locator(
-                Locator::from_str(s).map_err(|_| "Invalid GlobalLocator string format.")?,
-            ))
+            Err(ParseError::InvalidInstanceId {
+                id: id.to_string(),
+                expected: self,
+            })
+        }
+    }
+}
+
+impl Display for InstanceIdFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InstanceIdFormat::Any => write!(f, "a valid token"),
+            InstanceIdFormat::Numeric => write!(f, "numeric"),
+            InstanceIdFormat::Uuid => write!(f, "a UUID"),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+/// A service name and its instance id.
+///
+/// Deserialization goes through [`ServiceIdentifier::new`].
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone)]
+#[serde(try_from = "ServiceIdentifierRepr")]
 pub struct ServiceIdentifier {
+    service_name: String,
+    instance_id: InstanceId,
+}
+
+#[derive(Deserialize)]
+struct ServiceIdentifierRepr {
     service_name: String,
     instance_id: String,
 }
 
+impl TryFrom<ServiceIdentifierRepr> for ServiceIdentifier {
+    type Error = ParseError;
+    fn try_from(repr: ServiceIdentifierRepr) -> Result<Self, Self::Error> {
+        ServiceIdentifier::new(repr.service_name, repr.instance_id)
+    }
+}
+
+impl ServiceIdentifier {
+    /// Number of subject tokens a service identifier takes: name and instance id.
+    pub const TOKENS: usize = 2;
+
+    pub fn new(
+        service_name: impl Into<String>,
+        instance_id: impl Into<String>,
+    ) -> Result<Self, ParseError> {
+        let service_name = service_name.into();
+        validate_token(&service_name)?;
+        Ok(ServiceIdentifier {
+            service_name,
+            instance_id: InstanceId::from_str(&instance_id.into())?,
+        })
+    }
+
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    pub fn instance_id(&self) -> &str {
+        self.instance_id.as_str()
+    }
+
+    pub fn instance(&self) -> &InstanceId {
+        &self.instance_id
+    }
+
+    pub fn set_service_name(&mut self, service_name: impl Into<String>) -> Result<(), ParseError> {
+        let service_name = service_name.into();
+        validate_token(&service_name)?;
+        self.service_name = service_name;
+        Ok(())
+    }
+
+    pub fn set_instance_id(&mut self, instance_id: impl Into<String>) -> Result<(), ParseError> {
+        let instance_id = instance_id.into();
+        validate_token(&instance_id)?;
+        self.instance_id = InstanceId(instance_id);
+        Ok(())
+    }
+}
+
 impl Display for ServiceIdentifier {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}.{}", self.service_name, self.instance_id)
     }
 }
 
 impl FromStr for ServiceIdentifier {
-    type Err = &'static str;
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() == 2 {
-            Ok(ServiceIdentifier {
-                service_name: parts[0].to_string(),
-                instance_id: parts[1].to_string(),
-            })
-        } else {
-            Err("Invalid ServiceIdentifier string format. Expected: name.id")
+        match s.split_once('.') {
+            Some((service_name, instance_id)) if !instance_id.contains('.') => {
+                validate_token(service_name)?;
+                Ok(ServiceIdentifier {
+                    service_name: service_name.to_string(),
+                    instance_id: InstanceId::from_str(instance_id)?,
+                })
+            }
+            _ => Err(ParseError::InvalidServiceIdentifier),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+/// Serializes as its subject token, e.g. `"heartbeat"`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum PayloadType {
     Heartbeat,
     Data,
     Diagnostics,
     Command,
     Event,
-    Custom,
+    /// Payload type outside of the known set, stored verbatim.
+    ///
+    /// `"custom"` always parses to `Custom("custom")`. Any other unknown token is only
+    /// accepted when the `custom-payload-types` feature is enabled.
+    Custom(String),
 }
 
-impl Display for PayloadType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl PayloadType {
+    /// Number of subject tokens a payload type takes.
+    pub const TOKENS: usize = 1;
+
+    pub fn as_str(&self) -> &str {
         match self {
-            PayloadType::Heartbeat => write!(f, "heartbeat"),
-            PayloadType::Data => write!(f, "data"),
-            PayloadType::Diagnostics => write!(f, "diagnostics"),
-            PayloadType::Command => write!(f, "command"),
-            PayloadType::Event => write!(f, "event"),
-            PayloadType::Custom => write!(f, "custom"),
+            PayloadType::Heartbeat => "heartbeat",
+            PayloadType::Data => "data",
+            PayloadType::Diagnostics => "diagnostics",
+            PayloadType::Command => "command",
+            PayloadType::Event => "event",
+            PayloadType::Custom(custom) => custom,
         }
     }
 }
 
-impl FromStr for PayloadType {
-    type Err = &'static str;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "heartbeat" => Ok(PayloadType::Heartbeat),
-            "data" => Ok(PayloadType::Data),
-            "diagnostics" => Ok(PayloadType::Diagnostics),
-            "command" => Ok(PayloadType::Command),
-            "event" => Ok(PayloadType::Event),
-            "custom" => Ok(PayloadType::Custom),
-            _ => Err("Invalid PayloadType string"),
-        }
+impl PartialOrd for PayloadType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
-pub struct MyceliumSubject {
-    pub environment: Environment,
-    pub ownership_group: OwnershipGroup,
-    pub geo_locator: GeoLocator,
-    pub service_identifier: ServiceIdentifier,
-    pub payload_type: PayloadType,
-    pub payload_identifier: Vec<String>,
+impl Ord for PayloadType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let is_custom = |payload_type: &PayloadType| matches!(payload_type, PayloadType::Custom(_));
+        self.as_str()
+            .cmp(other.as_str())
+            .then_with(|| is_custom(self).cmp(&is_custom(other)))
+    }
 }
 
-impl Display for MyceliumSubject {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}.{}.{}.{}.{}",
-            self.environment,
-            self.ownership_group,
-            self.geo_locator,
-            self.service_identifier,
-            self.payload_type
-        )?;
+impl Display for PayloadType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
 
-        for part in &self.payload_identifier {
-            write!(f, ".{}", part)?;
-        }
-        Ok(())
+/// The known payload type `s` names, ignoring ASCII case. `custom` counts as known.
+fn known_payload_type(s: &str) -> Option<PayloadType> {
+    [
+        PayloadType::Heartbeat,
+        PayloadType::Data,
+        PayloadType::Diagnostics,
+        PayloadType::Command,
+        PayloadType::Event,
+        PayloadType::Custom("custom".to_string()),
+    ]
+    .into_iter()
+    .find(|payload_type| payload_type.as_str().eq_ignore_ascii_case(s))
+}
+
+pub(crate) fn validate_payload_type(s: &str) -> Result<(), ParseError> {
+    match s {
+        _ if known_payload_type(s).is_some() => Ok(()),
+        "" => Err(ParseError::EmptyToken),
+        #[cfg(feature = "custom-payload-types")]
+        _ => validate_token(s),
+        #[cfg(not(feature = "custom-payload-types"))]
+        _ => Err(ParseError::InvalidPayloadType(s.to_string())),
     }
 }
 
-impl FromStr for MyceliumSubject {
-    type Err = &'static str;
+/// Known payload types match ignoring ASCII case and display in lowercase, while a custom
+/// payload type keeps the case it was written with.
+impl FromStr for PayloadType {
+    type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.split('.').collect();
-        if parts.len() < 7 {
-            return Err("String too short to represent a local or global MyceliumSubject");
-        }
-        let environment = Environment::from_str(parts[0])?;
-        let ownership_group = OwnershipGroup::from_str(&format!("{}.{}", parts[1], parts[2]))?;
-
-        let geo_locator;
-        let mut global_offset = 0;
-        if parts[3] == "local" {
-            geo_locator = GeoLocator::Local;
-        } else if parts[3] == "global" {
-            geo_locator = GeoLocator::Global;
-        } else {
-            global_offset = 2;
-            if parts.len() < 9 {
-                return Err("String too short to represent a global MyceliumSubject");
-            }
-            let global_locator_str = format!("{}.{}.{}", parts[3], parts[4], parts[5]);
-            geo_locator = GeoLocator::Locator(Locator::from_str(&global_locator_str)?);
-        }
-        let service_identifier = ServiceIdentifier::from_str(&format!(
-            "{}.{}",
-            parts[4 + global_offset],
-            parts[5 + global_offset]
-        ))?;
+        validate_payload_type(s)?;
+        Ok(known_payload_type(s).unwrap_or_else(|| PayloadType::Custom(s.to_string())))
+    }
+}
 
-        let payload_type = PayloadType::from_str(parts[6 + global_offset])?;
+impl Serialize for PayloadType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
 
-        let payload_identifier: Vec<String> = parts[(7 + global_offset)..]
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
+impl<'de> Deserialize<'de> for PayloadType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        PayloadType::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+/// Payload identifier segments trailing the payload type of a subject.
+///
+/// Every segment is a valid token and the number of segments is bounded, by
+/// [`PayloadPath::DEFAULT_MAX_DEPTH`] unless a different limit is given. Deserialization goes
+/// through [`PayloadPath::new`].
+#[derive(Debug, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Default)]
+#[serde(try_from = "Vec<String>")]
+pub struct PayloadPath(Vec<String>);
+
+impl Serialize for PayloadPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl TryFrom<Vec<String>> for PayloadPath {
+    type Error = ParseError;
+    fn try_from(segments: Vec<String>) -> Result<Self, Self::Error> {
+        PayloadPath::new(segments)
+    }
+}
+
+impl PayloadPath {
+    pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+    pub fn new(segments: Vec<String>) -> Result<Self, ParseError> {
+        Self::with_max_depth(segments, Self::DEFAULT_MAX_DEPTH)
+    }
+
+    pub fn with_max_depth(segments: Vec<String>, max_depth: usize) -> Result<Self, ParseError> {
+        if segments.len() > max_depth {
+            return Err(ParseError::TooDeep {
+                depth: segments.len(),
+                max: max_depth,
+            });
+        }
+        for segment in &segments {
+            validate_token(segment)?;
+        }
+        Ok(PayloadPath(segments))
+    }
+
+    pub fn depth(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Appends a segment, keeping the default depth limit.
+    pub fn push(&mut self, segment: impl Into<String>) -> Result<(), ParseError> {
+        let segment = segment.into();
+        validate_token(&segment)?;
+        if self.0.len() >= Self::DEFAULT_MAX_DEPTH {
+            return Err(ParseError::TooDeep {
+                depth: self.0.len() + 1,
+                max: Self::DEFAULT_MAX_DEPTH,
+            });
+        }
+        self.0.push(segment);
+        Ok(())
+    }
+
+    /// Removes and returns the last segment.
+    pub fn pop(&mut self) -> Option<String> {
+        self.0.pop()
+    }
+}
+
+impl core::ops::Deref for PayloadPath {
+    type Target = [String];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
+impl<'a> IntoIterator for &'a PayloadPath {
+    type Item = &'a String;
+    type IntoIter = core::slice::Iter<'a, String>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Display for PayloadPath {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (index, segment) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A parsed Mycelium UNS subject.
+///
+/// The grammar is
+/// `environment.enterprise[.level]*.op_group.geo_locator.service_name.instance_id.payload_type[.payload_identifier]*`.
+/// The ownership group extends up to the first token that is `local`, `global`, `unknown` or shaped
+/// like an ISO 3166-2 code (two letters, a dash and one to three alphanumerics), so
+/// intermediate ownership levels must not look like one of those. When no such token is
+/// found before the first known [`PayloadType`], the ownership group is taken to be two levels
+/// and the fourth token is parsed as the geo-locator, so a mistyped geo-locator is reported
+/// instead of being read as more ownership levels. Levels past the op group's usual place
+/// therefore must not name a payload type either. A stray second geo-locator, as in `prod.abc.xyz.local.US-CA.south.abc...`,
+/// is rejected instead of shifting its tokens into the service identifier and payload: the
+/// service name must not be a known ISO code followed by a region, an identifier, a service
+/// identifier and a payload type. A service name that merely looks like an ISO code, such as
+/// `db-01`, is fine.
+///
+/// A [`Locator`] region may have several segments, so its extent is decided by the payload
+/// type: the service name is the first token, at least three past the ISO code, that is
+/// followed two tokens later by a known [`PayloadType`] (in any case). Everything between the
+/// ISO code and the token right before the service name is region, and that token is the
+/// identifier. When no known payload type is found, the region is a single segment. A
+/// subject missing its instance id, such as `...US-CA.north.bay-area.abc.plc-gateway.data`,
+/// therefore reads as a valid subject with a single-segment region and every later locator
+/// and service token shifted by one. Nothing in the tokens tells the two apart, so when
+/// instance ids have a known shape, parse with [`Self::from_str_with_instance_id_format`] to
+/// reject such subjects. With
+/// `custom-payload-types`, a subject with a custom payload type whose payload identifiers
+/// name a known payload type is therefore read with a longer region; [`Self::validate`]
+/// reports subjects built by hand that would not parse back the same way.
+///
+/// No token ever contains a `.`: every parser, constructor and setter rejects it instead of
+/// escaping it, so the displayed subject always parses back to the same value. A name such
+/// as `v1.2` has to be written with another character, like `v1-2`.
+///
+/// Subjects order lexicographically by their canonical token sequence, so sorting a list of
+/// subjects groups those sharing a prefix together. Distinct subjects with the same tokens,
+/// which only hand-built subjects can be, are ordered by their components, so the order
+/// agrees with equality.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct MyceliumSubject {
+    pub environment: Environment,
+    pub ownership_group: OwnershipGroup,
+    pub geo_locator: GeoLocator,
+    pub service_identifier: ServiceIdentifier,
+    pub payload_type: PayloadType,
+    pub payload_identifier: PayloadPath,
+}
+
+impl MyceliumSubject {
+    /// Fewest tokens in a subject, with a single-token geo-locator, a two-level
+    /// ownership group and no payload identifier.
+    pub const MIN_TOKENS: usize = Environment::TOKENS
+        + OwnershipGroup::MIN_TOKENS
+        + 1
+        + ServiceIdentifier::TOKENS
+        + PayloadType::TOKENS;
+    /// Most tokens [`MyceliumSubject::from_str`] accepts before giving up on the input.
+    ///
+    /// It leaves room for deep ownership groups and regions next to the
+    /// [`PayloadPath::DEFAULT_MAX_DEPTH`] payload identifiers, while bounding the work and
+    /// memory spent on a malformed subject.
+    pub const DEFAULT_MAX_TOKENS: usize = 128;
+    /// Fewest tokens in a subject with a full [`Locator`].
+    pub const MIN_LOCATOR_TOKENS: usize = Self::MIN_TOKENS - 1 + Locator::MIN_TOKENS;
+
+    /// Assembles a subject from already-typed components.
+    ///
+    /// The components are trusted as they are, only the payload identifiers are validated.
+    pub fn from_parts(
+        environment: Environment,
+        ownership_group: OwnershipGroup,
+        geo_locator: GeoLocator,
+        service_identifier: ServiceIdentifier,
+        payload_type: PayloadType,
+        payload_identifier: Vec<String>,
+    ) -> Result<Self, ParseError> {
         Ok(MyceliumSubject {
             environment,
             ownership_group,
             geo_locator,
             service_identifier,
             payload_type,
-            payload_identifier,
+            payload_identifier: PayloadPath::new(payload_identifier)?,
+        })
+    }
+
+    /// The canonical dot-separated tokens of this subject, in order.
+    ///
+    /// Yields the environment, every ownership level, then the geo-locator tokens, the
+    /// service name and instance id, the payload type and finally each payload identifier.
+    /// The geo-locator contributes one token for [`GeoLocator::Local`],
+    /// [`GeoLocator::Global`] and [`GeoLocator::Unknown`], for [`GeoLocator::Locator`] the ISO
+    /// code, every region segment and the identifier, and none for [`GeoLocator::Omitted`].
+    pub fn tokens(&self) -> impl Iterator<Item = Cow<'_, str>> {
+        let geo_first = self.geo_locator.first_token();
+        let (geo_region, geo_last): (&[String], _) = match &self.geo_locator {
+            GeoLocator::Locator(locator) => {
+                (&locator.op_region, Some(locator.op_identifier.as_str()))
+            }
+            _ => (&[], None),
+        };
+        [self.environment.as_str()]
+            .into_iter()
+            .chain(self.ownership_group.levels.iter().map(String::as_str))
+            .chain(geo_first)
+            .chain(geo_region.iter().map(String::as_str))
+            .chain(geo_last)
+            .chain([
+                self.service_identifier.service_name.as_str(),
+                self.service_identifier.instance_id.as_str(),
+                self.payload_type.as_str(),
+            ])
+            .chain(self.payload_identifier.iter().map(String::as_str))
+            .map(Cow::Borrowed)
+    }
+
+    /// Number of dot-separated tokens this subject serializes to.
+    pub fn token_count(&self) -> usize {
+        Environment::TOKENS
+            + self.ownership_group.levels.len()
+            + self.geo_locator.token_count()
+            + ServiceIdentifier::TOKENS
+            + PayloadType::TOKENS
+            + self.payload_identifier.len()
+    }
+
+    fn serialized_len(&self) -> usize {
+        self.tokens().map(|token| token.len()).sum::<usize>() + self.token_count() - 1
+    }
+
+    /// Appends the canonical string to `buf`, reserving the whole length up front.
+    ///
+    /// `to_string` goes through [`Display`] and grows its buffer as it writes;
+    /// `String::from(&subject)` uses this method instead and allocates once.
+    pub fn write_to(&self, buf: &mut String) {
+        buf.reserve(self.serialized_len());
+        for (index, token) in self.tokens().enumerate() {
+            if index > 0 {
+                buf.push(SUBJECT_SEPARATOR);
+            }
+            buf.push_str(&token);
+        }
+    }
+
+    /// Checks that the serialized subject fits in `max_bytes`, without building the string.
+    pub fn validate_length(&self, max_bytes: usize) -> Result<(), ParseError> {
+        let length = self.serialized_len();
+        if length > max_bytes {
+            Err(ParseError::TooLong {
+                length,
+                max: max_bytes,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The subject one level up the payload hierarchy.
+    ///
+    /// The parent is this subject with its last payload identifier removed. A subject without
+    /// payload identifiers is the root of its hierarchy and has no parent, so repeated calls
+    /// walk up to the bare `...payload_type` subject and then return `None`.
+    pub fn parent(&self) -> Option<MyceliumSubject> {
+        if self.payload_identifier.depth() == 0 {
+            return None;
+        }
+        let mut parent = self.clone();
+        parent.payload_identifier.pop();
+        Some(parent)
+    }
+
+    /// This subject with `extra` appended to its payload identifiers, each validated as by
+    /// [`PayloadPath::push`].
+    pub fn join<I, S>(&self, extra: I) -> Result<MyceliumSubject, ParseError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut subject = self.clone();
+        for segment in extra {
+            subject.payload_identifier.push(segment)?;
+        }
+        Ok(subject)
+    }
+
+    /// This subject with its case-insensitive tokens in their canonical case.
+    ///
+    /// The ISO code is uppercased and a custom payload type is lowercased, becoming a known
+    /// [`PayloadType`] when it names one. Every other token is kept as is. Parsing already
+    /// produces this form, so this is mostly useful for subjects assembled field by field.
+    pub fn canonicalize(&self) -> MyceliumSubject {
+        let mut subject = self.clone();
+        if let GeoLocator::Locator(locator) = &mut subject.geo_locator {
+            locator.iso_3166_2.make_ascii_uppercase();
+        }
+        if let PayloadType::Custom(custom) = &subject.payload_type {
+            let custom = custom.to_ascii_lowercase();
+            subject.payload_type =
+                PayloadType::from_str(&custom).unwrap_or(PayloadType::Custom(custom));
+        }
+        subject
+    }
+
+    /// The payload identifiers joined with `.`, e.g. `system.sub-system.sensor.value`, or
+    /// `None` when there are none.
+    pub fn payload_path(&self) -> Option<String> {
+        (self.payload_identifier.depth() > 0).then(|| self.payload_identifier.to_string())
+    }
+
+    pub fn payload_segments(&self) -> &[String] {
+        self.payload_identifier.segments()
+    }
+
+    /// The components keyed by name, for templating.
+    ///
+    /// Keys are `environment`, `enterprise`, `op_group`, `geo`, `service_name`, `instance_id`,
+    /// `payload_type` and, when there are payload identifiers, `payload_path`. Intermediate
+    /// ownership levels are not included, and neither is `geo` for [`GeoLocator::Omitted`].
+    pub fn to_field_map(&self) -> BTreeMap<&'static str, String> {
+        let mut fields = BTreeMap::new();
+        fields.insert("environment", self.environment.to_string());
+        fields.insert("enterprise", self.ownership_group.enterprise().to_string());
+        fields.insert("op_group", self.ownership_group.op_group().to_string());
+        if self.geo_locator != GeoLocator::Omitted {
+            fields.insert("geo", self.geo_locator.to_string());
+        }
+        fields.insert(
+            "service_name",
+            self.service_identifier.service_name().to_string(),
+        );
+        fields.insert(
+            "instance_id",
+            self.service_identifier.instance_id().to_string(),
+        );
+        fields.insert("payload_type", self.payload_type.to_string());
+        if let Some(payload_path) = self.payload_path() {
+            fields.insert("payload_path", payload_path);
+        }
+        fields
+    }
+
+    /// A copy of this subject in `environment`.
+    pub fn with_environment(&self, environment: Environment) -> MyceliumSubject {
+        MyceliumSubject {
+            environment,
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this subject with `payload_type`, keeping the payload identifiers.
+    pub fn with_payload_type(&self, payload_type: PayloadType) -> MyceliumSubject {
+        MyceliumSubject {
+            payload_type,
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this subject emitted by `service_identifier`.
+    pub fn with_service_identifier(
+        &self,
+        service_identifier: ServiceIdentifier,
+    ) -> MyceliumSubject {
+        MyceliumSubject {
+            service_identifier,
+            ..self.clone()
+        }
+    }
+
+    /// The environment and ownership group, e.g. `prod.abc.xyz`.
+    pub fn ownership_prefix(&self) -> String {
+        alloc::format!("{}.{}", self.environment, self.ownership_group)
+    }
+
+    /// The environment, ownership group and geo-locator, e.g. `prod.abc.xyz.local`.
+    pub fn geo_prefix(&self) -> String {
+        alloc::format!(
+            "{}.{}.{}",
+            self.environment,
+            self.ownership_group,
+            self.geo_locator
+        )
+    }
+
+    /// Pattern matching everything emitted by this service instance, whatever the payload.
+    pub fn service_wildcard(&self) -> SubjectPattern {
+        self.prefix_wildcard(3 + self.ownership_group.levels.len() + self.geo_locator.token_count())
+    }
+
+    /// Pattern matching everything emitted under this subject's ownership group.
+    pub fn ownership_wildcard(&self) -> SubjectPattern {
+        self.prefix_wildcard(1 + self.ownership_group.levels.len())
+    }
+
+    /// Pattern matching everything emitted in this subject's environment.
+    pub fn environment_wildcard(&self) -> SubjectPattern {
+        self.prefix_wildcard(1)
+    }
+
+    fn prefix_wildcard(&self, prefix_len: usize) -> SubjectPattern {
+        SubjectPattern::from_tokens(
+            self.tokens()
+                .take(prefix_len)
+                .map(|token| Token::Literal(token.into_owned()))
+                .chain([Token::MultiWildcard])
+                .collect(),
+        )
+    }
+
+    /// Re-checks every invariant that parsing enforces, without formatting the subject.
+    ///
+    /// Components can bypass validation, for example through deserialization, so this is the
+    /// way to make sure a subject built by hand displays as a string that parses back to it,
+    /// with [`Self::from_str_no_geo`] for [`GeoLocator::Omitted`].
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let levels = self.ownership_group.levels.len();
+        if levels < OwnershipGroup::MIN_TOKENS {
+            return Err(ParseError::InvalidOwnershipGroup);
+        }
+        let geo_index = Environment::TOKENS + levels;
+        let payload_index = geo_index + self.geo_locator.token_count() + ServiceIdentifier::TOKENS;
+        let max_index = payload_index + PayloadPath::DEFAULT_MAX_DEPTH;
+        let mut byte_offset = 0;
+        for (index, token) in self.tokens().enumerate() {
+            let at = |error: ParseError| error.at(index, byte_offset);
+            validate_token(&token).map_err(at)?;
+            if (subject_ref::FIRST_GEO_INDEX..geo_index).contains(&index)
+                && (subject_ref::is_geo_token(&token) || known_payload_type(&token).is_some())
+            {
+                // The level would be read back as the geo-locator, or would end the ownership
+                // group before it.
+                return Err(at(ParseError::InvalidOwnershipGroup));
+            }
+            if let GeoLocator::Locator(locator) = &self.geo_locator
+                && index == geo_index
+            {
+                validate_iso_code(&locator.iso_3166_2).map_err(at)?;
+                let region_len = subject_ref::region_len(self.tokens().skip(index + 1));
+                if region_len != locator.op_region.len() {
+                    // The region would be read back with a different number of segments.
+                    return Err(at(ParseError::InvalidLocator));
+                }
+            }
+            if index == payload_index - ServiceIdentifier::TOKENS
+                && subject_ref::is_geo_token(&token)
+                && subject_ref::starts_locator(
+                    &token,
+                    self.tokens().skip(index + 1).collect::<Vec<_>>().iter(),
+                )
+            {
+                // The service name would be read back as a second geo-locator.
+                return Err(at(ParseError::InvalidServiceIdentifier));
+            }
+            if self.geo_locator == GeoLocator::Omitted
+                && (Environment::TOKENS + OwnershipGroup::MIN_TOKENS + ServiceIdentifier::TOKENS
+                    ..payload_index)
+                    .contains(&index)
+                && known_payload_type(&token).is_some()
+            {
+                // `from_str_no_geo` would take this token for the payload type.
+                return Err(at(ParseError::ReservedWord(token.into_owned())));
+            }
+            if index == payload_index {
+                validate_payload_type(&token).map_err(at)?;
+            }
+            if index > max_index {
+                return Err(at(ParseError::TooDeep {
+                    depth: self.payload_identifier.depth(),
+                    max: PayloadPath::DEFAULT_MAX_DEPTH,
+                }));
+            }
+            byte_offset += token.len() + SUBJECT_SEPARATOR.len_utf8();
+        }
+        Ok(())
+    }
+
+    /// A hash of this subject that is the same across runs, processes and platforms.
+    ///
+    /// This is 64-bit FNV-1a over the UTF-8 bytes of the canonical subject string, tokens
+    /// joined by `.`. The algorithm is fixed, so values can be stored or shared between
+    /// services, for example to pick a shard.
+    pub fn stable_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let mut hash = OFFSET_BASIS;
+        let mut write = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(PRIME);
+            }
+        };
+        for (index, token) in self.tokens().enumerate() {
+            if index > 0 {
+                write(&[SUBJECT_SEPARATOR as u8]);
+            }
+            write(token.as_bytes());
+        }
+        hash
+    }
+
+    /// Whether a NATS subscription on `pattern` would receive this subject.
+    pub fn matches(&self, pattern: &SubjectPattern) -> bool {
+        let mut segments = self.tokens();
+        for token in pattern.tokens() {
+            match token {
+                Token::MultiWildcard => return segments.next().is_some(),
+                Token::Wildcard => {
+                    if segments.next().is_none() {
+                        return false;
+                    }
+                }
+                Token::Literal(literal) => {
+                    if segments.next().as_deref() != Some(literal.as_str()) {
+                        return false;
+                    }
+                }
+            }
+        }
+        segments.next().is_none()
+    }
+
+    /// Whether the tokens of this subject are a prefix of the tokens of `other`.
+    ///
+    /// Unlike [`Self::matches`] no wildcards are involved. Equal subjects are prefixes of each
+    /// other, and subjects whose geo-locators take a different number of tokens never are.
+    pub fn is_prefix_of(&self, other: &MyceliumSubject) -> bool {
+        self.geo_locator.token_count() == other.geo_locator.token_count()
+            && self.token_count() <= other.token_count()
+            && self.tokens().zip(other.tokens()).all(|(a, b)| a == b)
+    }
+
+    /// Bounds for [`BTreeMap::range`](alloc::collections::BTreeMap::range) or
+    /// [`BTreeSet::range`](alloc::collections::BTreeSet::range) covering this subject and every
+    /// subject whose tokens start with its tokens, such as its payload descendants.
+    ///
+    /// The range follows the token order, so like [`Self::is_prefix_of`] it is only about
+    /// tokens. The end bound is not a valid subject: its last token has a `\0` appended.
+    pub fn prefix_range(&self) -> (Bound<MyceliumSubject>, Bound<MyceliumSubject>) {
+        let mut end = self.clone();
+        match end.payload_identifier.0.last_mut() {
+            Some(last) => last.push('\0'),
+            None => {
+                let mut last = end.payload_type.as_str().to_string();
+                last.push('\0');
+                end.payload_type = PayloadType::Custom(last);
+            }
+        }
+        (Bound::Included(self.clone()), Bound::Excluded(end))
+    }
+}
+
+impl PartialOrd for MyceliumSubject {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MyceliumSubject {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Subjects built by hand can share their tokens without being equal, so ties are
+        // broken on the components that decide how the tokens split up.
+        self.tokens()
+            .cmp(other.tokens())
+            .then_with(|| {
+                self.ownership_group
+                    .levels
+                    .len()
+                    .cmp(&other.ownership_group.levels.len())
+            })
+            .then_with(|| self.geo_locator.cmp(&other.geo_locator))
+            .then_with(|| self.service_identifier.cmp(&other.service_identifier))
+            .then_with(|| self.payload_type.cmp(&other.payload_type))
+    }
+}
+
+/// `{}` writes the canonical dotted subject, while `{:#}` writes one labeled component per
+/// line for humans, e.g. `environment: prod`.
+impl Display for MyceliumSubject {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if f.alternate() {
+            return self.fmt_labeled(f);
+        }
+        write!(f, "{}.{}", self.environment, self.ownership_group)?;
+        if self.geo_locator != GeoLocator::Omitted {
+            write!(f, ".{}", self.geo_locator)?;
+        }
+        write!(f, ".{}.{}", self.service_identifier, self.payload_type)?;
+        debug_assert!(
+            self.tokens()
+                .all(|token| !token.contains(SUBJECT_SEPARATOR)),
+            "subject tokens must not contain the separator"
+        );
+
+        for part in &self.payload_identifier {
+            write!(f, ".{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+impl MyceliumSubject {
+    fn fmt_labeled(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let levels = self.ownership_group.levels();
+        writeln!(f, "environment: {}", self.environment)?;
+        writeln!(f, "enterprise: {}", self.ownership_group.enterprise())?;
+        for level in &levels[1..levels.len() - 1] {
+            writeln!(f, "level: {level}")?;
+        }
+        writeln!(f, "op_group: {}", self.ownership_group.op_group())?;
+        if self.geo_locator != GeoLocator::Omitted {
+            writeln!(f, "geo: {}", self.geo_locator)?;
+        }
+        writeln!(
+            f,
+            "service_name: {}",
+            self.service_identifier.service_name()
+        )?;
+        writeln!(f, "instance_id: {}", self.service_identifier.instance_id())?;
+        write!(f, "payload_type: {}", self.payload_type)?;
+        if let Some(payload_path) = self.payload_path() {
+            write!(f, "\npayload_path: {payload_path}")?;
+        }
+        Ok(())
+    }
+
+    /// Parses like [`MyceliumSubject::from_str`], but also rejects payload identifier segments
+    /// equal to `local`, `global`, `unknown` or an environment name.
+    pub fn from_str_strict(s: &str) -> Result<Self, ParseError> {
+        let subject = MyceliumSubject::from_str(s)?;
+        let first = subject.token_count() - subject.payload_identifier.depth();
+        let mut byte_offset = 0;
+        for (index, token) in s.split('.').enumerate() {
+            if index >= first {
+                validate_not_reserved(token).map_err(|error| error.at(index, byte_offset))?;
+            }
+            byte_offset += token.len() + 1;
+        }
+        Ok(subject)
+    }
+
+    /// Parses like [`MyceliumSubject::from_str`] after removing one leading and one trailing
+    /// `.`, if present, as left behind by copy and paste.
+    pub fn from_str_trimmed(s: &str) -> Result<Self, ParseError> {
+        let s = s.strip_prefix(SUBJECT_SEPARATOR).unwrap_or(s);
+        let s = s.strip_suffix(SUBJECT_SEPARATOR).unwrap_or(s);
+        MyceliumSubject::from_str(s)
+    }
+
+    /// Parses like [`MyceliumSubject::from_str`], but also checks the instance id against
+    /// `format`.
+    pub fn from_str_with_instance_id_format(
+        s: &str,
+        format: InstanceIdFormat,
+    ) -> Result<Self, ParseError> {
+        let subject = MyceliumSubject::from_str(s)?;
+        let index = subject.token_count() - subject.payload_identifier.depth() - 2;
+        format
+            .validate(subject.service_identifier.instance_id())
+            .map_err(|error| {
+                let byte_offset = s.split('.').take(index).map(|token| token.len() + 1).sum();
+                error.at(index, byte_offset)
+            })?;
+        Ok(subject)
+    }
+
+    /// Parses like [`MyceliumSubject::from_str`] with a different token limit than
+    /// [`Self::DEFAULT_MAX_TOKENS`].
+    pub fn from_str_with_max_tokens(s: &str, max_tokens: usize) -> Result<Self, ParseError> {
+        MyceliumSubjectRef::parse_with_max_tokens(s, max_tokens).map(|subject| subject.to_owned())
+    }
+
+    /// Parses a legacy subject that has no geo-locator section, as in
+    /// `prod.abc.xyz.plc-gateway.1.data`, into a subject with [`GeoLocator::Omitted`].
+    ///
+    /// [`MyceliumSubject::from_str`] cannot accept these: it would read the service name as the
+    /// geo-locator. Here the service name is instead the first token, at least five in, that
+    /// is followed two tokens later by a known [`PayloadType`], and without one the ownership
+    /// group is taken to be two levels. A subject that does have a geo-locator is rejected with
+    /// [`ParseError::InvalidOwnershipGroup`] at that token. The result displays in the same
+    /// legacy form, without a geo-locator.
+    pub fn from_str_no_geo(s: &str) -> Result<Self, ParseError> {
+        let tokens = s
+            .split(SUBJECT_SEPARATOR)
+            .scan(0, |byte_offset, token| {
+                let start = *byte_offset;
+                *byte_offset += token.len() + SUBJECT_SEPARATOR.len_utf8();
+                Some((start, token))
+            })
+            .enumerate();
+
+        let first_service = Environment::TOKENS + OwnershipGroup::MIN_TOKENS;
+        let mut service_at = None;
+        let mut found = 0;
+        for (index, (byte_offset, token)) in tokens.clone() {
+            if index == Self::DEFAULT_MAX_TOKENS {
+                return Err(ParseError::TooManyTokens {
+                    expected: Self::DEFAULT_MAX_TOKENS,
+                    found: index + 1,
+                }
+                .at(index, byte_offset));
+            }
+            validate_token(token).map_err(|error| error.at(index, byte_offset))?;
+            if index >= first_service + ServiceIdentifier::TOKENS
+                && service_at.is_none()
+                && known_payload_type(token).is_some()
+            {
+                service_at = Some(index - ServiceIdentifier::TOKENS);
+            }
+            found += 1;
+        }
+        let expected = Self::MIN_TOKENS - 1;
+        if found < expected {
+            return Err(ParseError::TooFewTokens { expected, found });
+        }
+        let service_at = service_at.unwrap_or(first_service);
+
+        let mut tokens = tokens;
+        let mut next = || {
+            tokens
+                .next()
+                .expect("token count checked before taking tokens")
+        };
+        let (_, (_, environment)) = next();
+        let environment = Environment::from_str(environment).map_err(|error| error.at(0, 0))?;
+        let mut levels = Vec::with_capacity(service_at - Environment::TOKENS);
+        for _ in Environment::TOKENS..service_at {
+            let (index, (byte_offset, level)) = next();
+            if subject_ref::is_geo_token(level) {
+                return Err(ParseError::InvalidOwnershipGroup.at(index, byte_offset));
+            }
+            levels.push(level.to_string());
+        }
+        let (index, (byte_offset, service_name)) = next();
+        let (_, (_, instance_id)) = next();
+        let (payload_index, (payload_offset, payload_type)) = next();
+        let after_service = s[byte_offset..].split(SUBJECT_SEPARATOR).skip(1);
+        if subject_ref::starts_locator(service_name, after_service) {
+            return Err(ParseError::InvalidServiceIdentifier.at(index, byte_offset));
+        }
+        let payload_type = PayloadType::from_str(payload_type)
+            .map_err(|error| error.at(payload_index, payload_offset))?;
+        let mut payload_identifier = Vec::new();
+        for (index, (byte_offset, segment)) in tokens {
+            if payload_identifier.len() == PayloadPath::DEFAULT_MAX_DEPTH {
+                let depth = found - payload_index - 1;
+                let max = PayloadPath::DEFAULT_MAX_DEPTH;
+                return Err(ParseError::TooDeep { depth, max }.at(index, byte_offset));
+            }
+            payload_identifier.push(segment.to_string());
+        }
+
+        Ok(MyceliumSubject {
+            environment,
+            ownership_group: OwnershipGroup { levels },
+            geo_locator: GeoLocator::Omitted,
+            service_identifier: ServiceIdentifier {
+                service_name: service_name.to_string(),
+                instance_id: InstanceId(instance_id.to_string()),
+            },
+            payload_type,
+            payload_identifier: PayloadPath(payload_identifier),
         })
     }
+
+    /// Parses a subject from raw bytes, such as a message frame, checking UTF-8 only once.
+    pub fn from_utf8(bytes: &[u8]) -> Result<Self, ParseError> {
+        MyceliumSubjectRef::from_utf8(bytes).map(|subject| subject.to_owned())
+    }
+
+    /// Parses a subject from tokens that are already split, with the same validation as
+    /// [`MyceliumSubject::from_str`]. A token containing `.` is rejected rather than split.
+    pub fn from_tokens<I, S>(tokens: I) -> Result<Self, ParseError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut subject = String::new();
+        for (index, token) in tokens.into_iter().enumerate() {
+            let token = token.as_ref();
+            if index > 0 {
+                subject.push('.');
+            }
+            if token.contains('.') {
+                return Err(ParseError::InvalidCharacter {
+                    token: token.to_string(),
+                    character: '.',
+                }
+                .at(index, subject.len()));
+            }
+            subject.push_str(token);
+        }
+        MyceliumSubject::from_str(&subject)
+    }
+
+    /// Parses a subject whose tokens are separated by `separator` instead of `.`.
+    ///
+    /// Tokens are validated exactly as by [`MyceliumSubject::from_str`], so they still may not
+    /// contain a `.`. Reported byte offsets are those of the dotted form, which match the input
+    /// when `separator` is ASCII.
+    pub fn from_str_with_separator(s: &str, separator: char) -> Result<Self, ParseError> {
+        if separator == SUBJECT_SEPARATOR {
+            return MyceliumSubject::from_str(s);
+        }
+        MyceliumSubject::from_tokens(s.split(separator))
+    }
+
+    /// Displays the subject with `separator` between tokens instead of `.`.
+    ///
+    /// The output only parses back with [`Self::from_str_with_separator`] if no token contains
+    /// `separator`.
+    pub fn display_with_separator(&self, separator: char) -> impl Display + '_ {
+        struct WithSeparator<'a>(&'a MyceliumSubject, char);
+
+        impl Display for WithSeparator<'_> {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                for (index, token) in self.0.tokens().enumerate() {
+                    if index > 0 {
+                        write!(f, "{}", self.1)?;
+                    }
+                    write!(f, "{token}")?;
+                }
+                Ok(())
+            }
+        }
+
+        WithSeparator(self, separator)
+    }
+
+    /// Parses like [`MyceliumSubject::from_str`], but keeps going after an error and returns
+    /// every problem found, ordered by position with at most one error per token.
+    pub fn parse_all_errors(s: &str) -> Result<Self, Vec<ParseError>> {
+        let mut errors = Vec::new();
+        let res = MyceliumSubjectRef::parse_reporting(s, Self::DEFAULT_MAX_TOKENS, |error| {
+            errors.push(error);
+            Ok(())
+        });
+        match res {
+            Ok(subject) if errors.is_empty() => return Ok(subject.to_owned()),
+            Ok(_) => {}
+            Err(error) => errors.push(error),
+        }
+        errors.sort_by_key(ParseError::token_index);
+        errors.dedup_by_key(|error| error.token_index());
+        Err(errors)
+    }
+}
+
+impl FromStr for MyceliumSubject {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MyceliumSubjectRef::parse(s).map(|subject| subject.to_owned())
+    }
+}
+
+impl TryFrom<&str> for MyceliumSubject {
+    type Error = ParseError;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        MyceliumSubject::from_str(value)
+    }
+}
+
+impl TryFrom<String> for MyceliumSubject {
+    type Error = ParseError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        MyceliumSubject::from_str(&value)
+    }
+}
+
+/// Writes the canonical string into a buffer sized up front, without going through
+/// [`Display`].
+impl From<&MyceliumSubject> for String {
+    fn from(subject: &MyceliumSubject) -> Self {
+        let mut s = String::new();
+        subject.write_to(&mut s);
+        s
+    }
+}
+
+/// Grows the enterprise's string into the whole subject instead of allocating a new buffer.
+impl From<MyceliumSubject> for String {
+    fn from(mut subject: MyceliumSubject) -> Self {
+        let len = subject.serialized_len();
+        let mut s = core::mem::take(&mut subject.ownership_group.levels[0]);
+        s.reserve_exact(len - s.len());
+        s.insert(0, SUBJECT_SEPARATOR);
+        s.insert_str(0, subject.environment.as_str());
+        // The enterprise is already written, and its slot is now empty.
+        for token in subject.tokens().skip(Environment::TOKENS + 1) {
+            s.push(SUBJECT_SEPARATOR);
+            s.push_str(&token);
+        }
+        s
+    }
+}
+
+/// Yields the canonical tokens as owned strings, moving them out of the subject.
+impl IntoIterator for MyceliumSubject {
+    type Item = String;
+    type IntoIter = alloc::vec::IntoIter<String>;
+    fn into_iter(self) -> Self::IntoIter {
+        let mut tokens = Vec::with_capacity(self.token_count());
+        tokens.push(self.environment.to_string());
+        tokens.extend(self.ownership_group.levels);
+        match self.geo_locator {
+            GeoLocator::Local => tokens.push("local".to_string()),
+            GeoLocator::Global => tokens.push("global".to_string()),
+            GeoLocator::Unknown => tokens.push("unknown".to_string()),
+            GeoLocator::Locator(locator) => {
+                tokens.push(locator.iso_3166_2);
+                tokens.extend(locator.op_region);
+                tokens.push(locator.op_identifier);
+            }
+            GeoLocator::Omitted => {}
+        }
+        tokens.push(self.service_identifier.service_name);
+        tokens.push(self.service_identifier.instance_id.0);
+        tokens.push(match self.payload_type {
+            PayloadType::Custom(custom) => custom,
+            payload_type => payload_type.to_string(),
+        });
+        tokens.extend(self.payload_identifier.0);
+        tokens.into_iter()
+    }
+}
+
+/// Yields copies of the canonical tokens, see [`MyceliumSubject::tokens`] for borrowed ones.
+impl IntoIterator for &MyceliumSubject {
+    type Item = String;
+    type IntoIter = alloc::vec::IntoIter<String>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.tokens()
+            .map(Cow::into_owned)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
 }
 
 #[cfg(test)]
@@ -340,6 +1927,7 @@ mod test {
         assert!(res.is_err());
     }
 
+    #[cfg(not(feature = "custom-payload-types"))]
     #[test]
     fn local_from_string_fail_bad_payload_id() {
         let subject_string = "prod.abc.xyz.local.plc-gateway.1.datas";
@@ -377,10 +1965,1775 @@ mod test {
         assert!(res.is_err());
     }
 
+    #[cfg(not(feature = "custom-payload-types"))]
     #[test]
     fn global_from_string_fail_bad_payload_id() {
         let subject_string = "prod.abc.xyz.global.plc-gateway.1.datas";
         let res = MyceliumSubject::from_str(subject_string);
         assert!(res.is_err());
     }
+
+    #[test]
+    fn ownership_group_accessors() {
+        let mut group = OwnershipGroup::from_str("abc.xyz").unwrap();
+        assert_eq!("abc", group.enterprise());
+        assert_eq!("xyz", group.op_group());
+        group.set_enterprise("def").unwrap();
+        group.set_op_group("uvw").unwrap();
+        assert_eq!("def.uvw", group.to_string());
+        assert!(group.set_enterprise("").is_err());
+        assert!(group.set_op_group("a.b").is_err());
+        assert_eq!("def.uvw", group.to_string());
+    }
+
+    #[test]
+    fn locator_accessors() {
+        let mut locator = Locator::from_str("US-CA.south.abc").unwrap();
+        assert_eq!("US-CA", locator.iso_3166_2());
+        assert_eq!(["south"], locator.op_region());
+        assert_eq!("abc", locator.op_identifier());
+        locator.set_iso_3166_2("US-NY").unwrap();
+        locator.set_op_region("north").unwrap();
+        locator.set_op_identifier("def").unwrap();
+        assert_eq!("US-NY.north.def", locator.to_string());
+        #[cfg(feature = "strict-iso")]
+        assert!(locator.set_iso_3166_2("US-AA").is_err());
+        assert!(locator.set_iso_3166_2("USNY").is_err());
+        assert!(locator.set_op_region("").is_err());
+        assert!(locator.set_op_identifier("a.b").is_err());
+        assert_eq!("US-NY.north.def", locator.to_string());
+    }
+
+    #[test]
+    fn service_identifier_accessors() {
+        let mut service = ServiceIdentifier::from_str("plc-gateway.1").unwrap();
+        assert_eq!("plc-gateway", service.service_name());
+        assert_eq!("1", service.instance_id());
+        service.set_service_name("hmi").unwrap();
+        service.set_instance_id("2").unwrap();
+        assert_eq!("hmi.2", service.to_string());
+        assert!(service.set_service_name("").is_err());
+        assert!(service.set_instance_id("").is_err());
+    }
+
+    #[test]
+    fn payload_type_custom_round_trip() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.custom.value";
+        let res = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(PayloadType::Custom("custom".to_string()), res.payload_type);
+        assert_eq!(subject_string, res.to_string());
+    }
+
+    #[cfg(feature = "custom-payload-types")]
+    #[test]
+    fn payload_type_unknown_preserved() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.telemetry.value";
+        let res = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(
+            PayloadType::Custom("telemetry".to_string()),
+            res.payload_type
+        );
+        assert_eq!(subject_string, res.to_string());
+        assert!(PayloadType::from_str("").is_err());
+    }
+
+    #[cfg(not(feature = "custom-payload-types"))]
+    #[test]
+    fn payload_type_unknown_rejected() {
+        assert!(PayloadType::from_str("telemetry").is_err());
+    }
+
+    #[test]
+    fn parse_error_kinds() {
+        assert_eq!(
+            &ParseError::InvalidEnvironment("production".to_string()),
+            MyceliumSubject::from_str("production.abc.xyz.local.plc-gateway.1.data")
+                .unwrap_err()
+                .kind()
+        );
+        #[cfg(feature = "strict-iso")]
+        assert_eq!(
+            &ParseError::InvalidIsoCode("US-AA".to_string()),
+            MyceliumSubject::from_str("prod.abc.xyz.US-AA.south.abc.plc-gateway.1.data")
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            Err(ParseError::TooFewTokens {
+                expected: 7,
+                found: 6
+            }),
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidOwnershipGroup),
+            OwnershipGroup::from_str("abc")
+        );
+    }
+
+    #[test]
+    fn parse_error_position() {
+        #[cfg(feature = "strict-iso")]
+        {
+            let res = MyceliumSubject::from_str("prod.abc.xyz.US-AA.south.abc.plc-gateway.1.data")
+                .unwrap_err();
+            assert_eq!(Some(3), res.token_index());
+            assert_eq!(Some(13), res.byte_offset());
+        }
+        let res = MyceliumSubject::from_str("prod.abc.xyz.USCA.south.abc.plc-gateway.1.data")
+            .unwrap_err();
+        assert_eq!(Some(3), res.token_index());
+        assert_eq!(Some(13), res.byte_offset());
+
+        let res = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.").unwrap_err();
+        assert_eq!(Some(6), res.token_index());
+        assert_eq!(Some(33), res.byte_offset());
+    }
+
+    #[test]
+    fn geo_locator_from_string() {
+        assert_eq!(GeoLocator::Local, GeoLocator::from_str("local").unwrap());
+        assert_eq!(GeoLocator::Global, GeoLocator::from_str("global").unwrap());
+        assert_eq!(
+            "global",
+            GeoLocator::from_str("global").unwrap().to_string()
+        );
+        assert_eq!(
+            "US-CA.south.abc",
+            GeoLocator::from_str("US-CA.south.abc").unwrap().to_string()
+        );
+        assert!(GeoLocator::from_str("regional").is_err());
+    }
+
+    #[test]
+    fn geo_locator_unknown() {
+        let unknown = GeoLocator::from_str("unknown").unwrap();
+        assert_eq!(GeoLocator::Unknown, unknown);
+        assert_ne!(GeoLocator::Local, unknown);
+        assert_ne!(GeoLocator::Global, unknown);
+        assert_eq!("unknown", unknown.to_string());
+
+        let subject_string = "prod.abc.north.xyz.unknown.plc-gateway.1.data.sensor";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(GeoLocator::Unknown, subject.geo_locator);
+        assert_eq!(["abc", "north", "xyz"], subject.ownership_group.levels());
+        assert_eq!(subject_string, subject.to_string());
+        assert_eq!(subject.token_count(), subject.tokens().count());
+        assert_eq!(
+            subject,
+            MyceliumSubject::from_tokens(subject.clone()).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_str_no_geo_legacy() {
+        let subject_string = "prod.abc.xyz.plc-gateway.1.data";
+        let subject = MyceliumSubject::from_str_no_geo(subject_string).unwrap();
+        assert_eq!(GeoLocator::Omitted, subject.geo_locator);
+        assert_eq!(GeoScope::Omitted, subject.geo_locator.scope());
+        assert_eq!(subject_string, subject.to_string());
+        assert_eq!(6, subject.token_count());
+        assert_eq!(subject.token_count(), subject.tokens().count());
+        assert_eq!(Ok(()), subject.validate());
+        assert_ne!(
+            MyceliumSubject::from_str("prod.abc.xyz.unknown.plc-gateway.1.data").unwrap(),
+            subject
+        );
+
+        let subject_string = "prod.abc.north.xyz.plc-gateway.1.data.sensor.value";
+        let subject = MyceliumSubject::from_str_no_geo(subject_string).unwrap();
+        assert_eq!(["abc", "north", "xyz"], subject.ownership_group.levels());
+        assert_eq!("plc-gateway", subject.service_identifier.service_name());
+        assert_eq!(PayloadType::Data, subject.payload_type);
+        assert_eq!(Some("sensor.value".to_string()), subject.payload_path());
+        assert_eq!(subject_string, subject.to_string());
+        assert_eq!(subject_string, String::from(&subject));
+        assert!(!subject.to_field_map().contains_key("geo"));
+        assert_eq!(
+            subject,
+            MyceliumSubject::from_bytes(&subject.to_bytes()).unwrap()
+        );
+
+        // The strict parser still requires the geo-locator.
+        assert!(MyceliumSubject::from_str(subject_string).is_err());
+
+        let mut subject = subject;
+        subject.service_identifier.set_instance_id("event").unwrap();
+        assert_eq!(Some(5), subject.validate().unwrap_err().token_index());
+    }
+
+    #[test]
+    fn from_str_no_geo_fail() {
+        let res =
+            MyceliumSubject::from_str_no_geo("prod.abc.xyz.local.plc-gateway.1.data").unwrap_err();
+        assert_eq!(&ParseError::InvalidOwnershipGroup, res.kind());
+        assert_eq!(Some(3), res.token_index());
+
+        let res = MyceliumSubject::from_str_no_geo("prod.abc.xyz.plc-gateway..data").unwrap_err();
+        assert_eq!(&ParseError::EmptyToken, res.kind());
+        assert_eq!(Some(4), res.token_index());
+        assert_eq!(Some(25), res.byte_offset());
+
+        assert_eq!(
+            Err(ParseError::TooFewTokens {
+                expected: 6,
+                found: 5
+            }),
+            MyceliumSubject::from_str_no_geo("prod.abc.xyz.plc-gateway.1")
+        );
+    }
+
+    #[test]
+    fn join_payload_identifiers() {
+        let base =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system")
+                .unwrap();
+        assert_eq!(
+            MyceliumSubject::from_str(
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor.value"
+            )
+            .unwrap(),
+            base.join(["sensor", "value"]).unwrap()
+        );
+        assert_eq!(base, base.join(Vec::<String>::new()).unwrap());
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            base.join(["sensor".to_string(), String::new()])
+        );
+    }
+
+    #[test]
+    fn prefix_range_query() {
+        let subject_strings = [
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.local.plc-gateway.1.data.sensor",
+            "prod.abc.xyz.local.plc-gateway.1.data.sensor.value",
+            "prod.abc.xyz.local.plc-gateway.1.data.sensor-a",
+            "prod.abc.xyz.local.plc-gateway.1.data.sensor2",
+            "prod.abc.xyz.local.plc-gateway.1.data.system",
+            "prod.abc.xyz.local.plc-gateway.1.diagnostics",
+            "prod.abc.xyz.local.plc-gateway.2.data.sensor",
+        ];
+        let map: alloc::collections::BTreeMap<_, _> = subject_strings
+            .iter()
+            .map(|s| (MyceliumSubject::from_str(s).unwrap(), *s))
+            .collect();
+
+        let sensor = MyceliumSubject::from_str(subject_strings[1]).unwrap();
+        let found: Vec<_> = map.range(sensor.prefix_range()).map(|(_, s)| *s).collect();
+        assert_eq!(subject_strings[1..=2], found);
+
+        let data = MyceliumSubject::from_str(subject_strings[0]).unwrap();
+        let found: Vec<_> = map.range(data.prefix_range()).map(|(_, s)| *s).collect();
+        assert_eq!(subject_strings[0..=5], found);
+    }
+
+    #[test]
+    fn single_token_geo_followed_by_geo_tokens() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.data.US-CA.south.abc";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(GeoLocator::Local, subject.geo_locator);
+        assert_eq!("plc-gateway", subject.service_identifier.service_name());
+        assert_eq!(PayloadType::Data, subject.payload_type);
+        assert_eq!(Some("US-CA.south.abc".to_string()), subject.payload_path());
+        assert_eq!(Ok(()), subject.validate());
+
+        // Service names shaped like an ISO code are not a second geo-locator.
+        for subject_string in [
+            "prod.abc.xyz.local.db-01.1.data",
+            "prod.abc.xyz.local.ui-1.1.data.US-CA.south.abc",
+            "prod.abc.xyz.global.US-CA.1.data.sensor",
+        ] {
+            let subject = MyceliumSubject::from_str(subject_string).unwrap();
+            assert_eq!(subject_string, subject.to_string());
+            assert_eq!(Ok(()), subject.validate());
+        }
+
+        let res =
+            MyceliumSubject::from_str("prod.abc.xyz.local.US-CA.south.abc.plc-gateway.1.data")
+                .unwrap_err();
+        assert_eq!(&ParseError::InvalidServiceIdentifier, res.kind());
+        assert_eq!(Some(4), res.token_index());
+        assert_eq!(Some(19), res.byte_offset());
+
+        let mut subject =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.south.data.abc.gw.1.data")
+                .unwrap();
+        subject
+            .service_identifier
+            .set_service_name("US-TX")
+            .unwrap();
+        let res = subject.validate().unwrap_err();
+        assert_eq!(&ParseError::InvalidServiceIdentifier, res.kind());
+        assert_eq!(Some(4), res.token_index());
+    }
+
+    #[test]
+    fn geo_locator_scope() {
+        assert_eq!(GeoScope::Local, GeoLocator::Local.scope());
+        assert_eq!(GeoScope::Global, GeoLocator::Global.scope());
+        assert_eq!(GeoScope::Regional, GeoLocator::Unknown.scope());
+        assert_eq!(GeoScope::Omitted, GeoLocator::Omitted.scope());
+        assert_eq!(
+            GeoScope::Regional,
+            GeoLocator::locator("US-CA", "south", "abc")
+                .unwrap()
+                .scope()
+        );
+    }
+
+    #[test]
+    fn from_string_fail_empty_tokens() {
+        let leading =
+            MyceliumSubject::from_str(".prod.abc.xyz.local.plc-gateway.1.data").unwrap_err();
+        assert_eq!(&ParseError::EmptyToken, leading.kind());
+        assert_eq!(Some(0), leading.token_index());
+
+        let doubled = MyceliumSubject::from_str("prod..xyz.local.plc-gateway.1.data").unwrap_err();
+        assert_eq!(&ParseError::EmptyToken, doubled.kind());
+        assert_eq!(Some(1), doubled.token_index());
+        assert_eq!(Some(5), doubled.byte_offset());
+
+        let payload =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.system..value")
+                .unwrap_err();
+        assert_eq!(&ParseError::EmptyToken, payload.kind());
+        assert_eq!(Some(8), payload.token_index());
+
+        let trailing =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.").unwrap_err();
+        assert_eq!(&ParseError::EmptyToken, trailing.kind());
+        assert_eq!(Some(7), trailing.token_index());
+    }
+
+    #[test]
+    fn components_from_string_fail_empty_tokens() {
+        assert_eq!(Err(ParseError::EmptyToken), Environment::from_str(""));
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            OwnershipGroup::from_str(".xyz")
+        );
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            OwnershipGroup::from_str("abc.")
+        );
+        assert_eq!(Err(ParseError::EmptyToken), Locator::from_str("US-CA..abc"));
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            ServiceIdentifier::from_str("plc-gateway.")
+        );
+        assert_eq!(Err(ParseError::EmptyToken), PayloadType::from_str(""));
+    }
+
+    #[test]
+    fn from_string_fail_trailing_dot() {
+        let res = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.");
+        assert!(res.is_err());
+        let res = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.system.");
+        assert!(res.is_err());
+        let res =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.system").unwrap();
+        assert!(!res.to_string().ends_with('.'));
+    }
+
+    #[test]
+    fn subject_matches_pattern() {
+        let cases = [
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                true,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "prod.abc.xyz.local.*.*.data",
+                true,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "*.*.*.*.*.*.*",
+                true,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data.a.b",
+                "prod.abc.xyz.local.plc-gateway.1.data.>",
+                true,
+            ),
+            ("prod.abc.xyz.local.plc-gateway.1.data.a", "prod.>", true),
+            (
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+                "prod.abc.xyz.US-CA.*.*.plc-gateway.1.>",
+                true,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "prod.abc.xyz.local.plc-gateway.1.data.>",
+                false,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data.a",
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                false,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data.a",
+                "prod.abc.xyz.local.plc-gateway.1.data.*.*",
+                false,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "dev.abc.xyz.local.plc-gateway.1.data",
+                false,
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "prod.abc.xyz.global.*.*.data",
+                false,
+            ),
+            (
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+                "prod.abc.xyz.*.plc-gateway.1.>",
+                false,
+            ),
+        ];
+        for (subject, pattern, expected) in cases {
+            let subject = MyceliumSubject::from_str(subject).unwrap();
+            let pattern = SubjectPattern::from_str(pattern).unwrap();
+            assert_eq!(expected, subject.matches(&pattern), "{subject} ~ {pattern}");
+        }
+    }
+
+    #[test]
+    fn subject_try_from() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.data.system";
+        let expected = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(
+            Ok(expected.clone()),
+            MyceliumSubject::try_from(subject_string)
+        );
+        let res: Result<MyceliumSubject, _> = subject_string.to_string().try_into();
+        assert_eq!(Ok(expected), res);
+        assert!(MyceliumSubject::try_from("prod.abc.xyz.local.plc-gateway.1".to_string()).is_err());
+    }
+
+    #[test]
+    fn subjects_sort_by_tokens() {
+        let mut subjects: Vec<MyceliumSubject> = [
+            "staging.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.local.plc-gateway.1.data.b",
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.global.plc-gateway.1.data",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+            "prod.abc.xyz.local.plc-gateway.1.data.a",
+            "dev.abc.xyz.local.plc-gateway.1.event",
+            "prod.abc.xyz.local.plc-gateway.1.command",
+            "prod.abc.xyz.north.local.plc-gateway.1.data",
+        ]
+        .iter()
+        .map(|s| MyceliumSubject::from_str(s).unwrap())
+        .collect();
+        subjects.sort();
+        let sorted: Vec<String> = subjects.iter().map(|s| s.to_string()).collect();
+        let mut expected = sorted.clone();
+        expected.sort_by(|a, b| a.split('.').cmp(b.split('.')));
+        assert_eq!(
+            vec![
+                "dev.abc.xyz.local.plc-gateway.1.event",
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+                "prod.abc.xyz.global.plc-gateway.1.data",
+                "prod.abc.xyz.local.plc-gateway.1.command",
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "prod.abc.xyz.local.plc-gateway.1.data.a",
+                "prod.abc.xyz.local.plc-gateway.1.data.b",
+                "prod.abc.xyz.north.local.plc-gateway.1.data",
+                "staging.abc.xyz.local.plc-gateway.1.data",
+            ],
+            sorted
+        );
+        assert_eq!(expected, sorted);
+    }
+
+    #[test]
+    fn subjects_with_same_tokens_order_apart() {
+        let local = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        let mut omitted = local.clone();
+        omitted.ownership_group = OwnershipGroup {
+            levels: vec!["abc".to_string(), "xyz".to_string(), "local".to_string()],
+        };
+        omitted.geo_locator = GeoLocator::Omitted;
+        assert!(local.tokens().eq(omitted.tokens()));
+        assert_ne!(local, omitted);
+        assert_ne!(Ordering::Equal, local.cmp(&omitted));
+        assert_eq!(local.cmp(&omitted), omitted.cmp(&local).reverse());
+
+        let set: alloc::collections::BTreeSet<_> = [local, omitted].into_iter().collect();
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn subject_as_map_key() {
+        let mut counts = std::collections::HashMap::new();
+        for s in [
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.global.plc-gateway.1.data",
+        ] {
+            *counts
+                .entry(MyceliumSubject::from_str(s).unwrap())
+                .or_insert(0) += 1;
+        }
+        let key = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert_eq!(Some(&2), counts.get(&key));
+    }
+
+    #[test]
+    fn subject_tokens() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.global.plc-gateway.1.event.alarm",
+        ];
+        for subject_string in subject_strings {
+            let res = MyceliumSubject::from_str(subject_string).unwrap();
+            let tokens: Vec<Cow<str>> = res.tokens().collect();
+            assert_eq!(subject_string.split('.').collect::<Vec<_>>(), tokens);
+        }
+    }
+
+    #[test]
+    fn subject_token_count() {
+        let subject_strings = [
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.local.plc-gateway.1.data.system",
+            "prod.abc.xyz.global.plc-gateway.1.data",
+            "prod.abc.xyz.global.plc-gateway.1.data.system.sub-system",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        ];
+        for subject_string in subject_strings {
+            let res = MyceliumSubject::from_str(subject_string).unwrap();
+            assert_eq!(subject_string.split('.').count(), res.token_count());
+            assert_eq!(res.tokens().count(), res.token_count());
+        }
+    }
+
+    #[test]
+    fn subject_validate_length() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let res = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(Ok(()), res.validate_length(255));
+        assert_eq!(Ok(()), res.validate_length(subject_string.len()));
+        assert_eq!(
+            Err(ParseError::TooLong {
+                length: subject_string.len(),
+                max: 20
+            }),
+            res.validate_length(20)
+        );
+    }
+
+    #[test]
+    fn components_from_string_fail_reserved_characters() {
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "plc gateway".to_string(),
+                character: ' '
+            }),
+            ServiceIdentifier::from_str("plc gateway.1")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "foo*bar".to_string(),
+                character: '*'
+            }),
+            OwnershipGroup::from_str("foo*bar.xyz")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "so\tuth".to_string(),
+                character: '\t'
+            }),
+            Locator::from_str("US-CA.so\tuth.abc")
+        );
+        assert!(OwnershipGroup::from_str("abc.x>z").is_err());
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "plc\0gateway".to_string(),
+                character: '\0'
+            }),
+            ServiceIdentifier::from_str("plc\0gateway.1")
+        );
+        assert!(ServiceIdentifier::from_str("plc-gateway.1\x7f").is_err());
+
+        let res = MyceliumSubject::from_str("prod.abc.xyz.local.plc gateway.1.data").unwrap_err();
+        assert_eq!(Some(4), res.token_index());
+        let res =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.a*").unwrap_err();
+        assert_eq!(Some(7), res.token_index());
+    }
+
+    #[test]
+    fn setters_fail_reserved_characters() {
+        let mut service = ServiceIdentifier::from_str("plc-gateway.1").unwrap();
+        assert!(service.set_service_name("plc gateway").is_err());
+        assert!(service.set_instance_id("1>").is_err());
+        assert_eq!("plc-gateway.1", service.to_string());
+    }
+
+    #[test]
+    fn environment_from_string_case_insensitive() {
+        assert_eq!(Ok(Environment::Production), Environment::from_str("PROD"));
+        assert_eq!(Ok(Environment::Staging), Environment::from_str("Staging"));
+        assert_eq!(Ok(Environment::Dev), Environment::from_str("dev"));
+        assert_eq!("prod", Environment::from_str("PROD").unwrap().to_string());
+        assert_eq!(
+            "staging",
+            Environment::from_str("Staging").unwrap().to_string()
+        );
+
+        let res = MyceliumSubject::from_str("PROD.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert_eq!("prod.abc.xyz.local.plc-gateway.1.data", res.to_string());
+    }
+
+    #[test]
+    fn environment_from_string_exact() {
+        assert_eq!(
+            Ok(Environment::Production),
+            Environment::from_str_exact("prod")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidEnvironment("PROD".to_string())),
+            Environment::from_str_exact("PROD")
+        );
+        assert_eq!(Err(ParseError::EmptyToken), Environment::from_str_exact(""));
+    }
+
+    #[test]
+    fn environment_test_and_qa() {
+        assert_eq!(Ok(Environment::Test), Environment::from_str("test"));
+        assert_eq!(Ok(Environment::Qa), Environment::from_str("qa"));
+        for subject_string in [
+            "test.abc.xyz.local.plc-gateway.1.data",
+            "qa.abc.xyz.global.plc-gateway.1.data.system",
+        ] {
+            let res = MyceliumSubject::from_str(subject_string).unwrap();
+            assert_eq!(subject_string, res.to_string());
+        }
+    }
+
+    #[cfg(feature = "strict-iso")]
+    #[test]
+    fn locator_strict_iso_rejects_unknown_code() {
+        assert_eq!(
+            Err(ParseError::InvalidIsoCode("US-AA".to_string())),
+            Locator::from_str("US-AA.south.abc")
+        );
+    }
+
+    #[cfg(not(feature = "strict-iso"))]
+    #[test]
+    fn locator_lenient_iso_accepts_unknown_code() {
+        let res = Locator::from_str("US-AA.south.abc").unwrap();
+        assert_eq!("US-AA.south.abc", res.to_string());
+        assert!(Locator::from_str("USAA.south.abc").is_err());
+        assert!(Locator::from_str("US-.south.abc").is_err());
+    }
+
+    #[cfg(feature = "strict-iso")]
+    #[test]
+    fn locator_subdivision() {
+        let locator = Locator::from_str("US-CA.south.abc").unwrap();
+        let subdivision = locator.subdivision().unwrap();
+        assert_eq!("US-CA", subdivision.code);
+        assert_eq!("United States", subdivision.country_name);
+        assert_eq!(Some("US"), locator.country_code());
+        assert_eq!(Some("California"), locator.subdivision_name());
+    }
+
+    #[test]
+    fn locator_from_string_normalizes_iso_code() {
+        let lower = Locator::from_str("us-ca.south.abc").unwrap();
+        let upper = Locator::from_str("US-CA.south.abc").unwrap();
+        assert_eq!(upper, lower);
+        assert_eq!("US-CA.south.abc", lower.to_string());
+
+        let res =
+            MyceliumSubject::from_str("prod.abc.xyz.us-ca.south.abc.plc-gateway.1.data").unwrap();
+        assert_eq!(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data",
+            res.to_string()
+        );
+    }
+
+    #[test]
+    fn instance_id_numeric() {
+        let service = ServiceIdentifier::from_str("plc-gateway.42").unwrap();
+        assert_eq!(Some(42), service.instance().as_u64());
+        assert_eq!("42", service.instance_id());
+        assert_eq!("plc-gateway.42", service.to_string());
+    }
+
+    #[test]
+    fn instance_id_alphanumeric() {
+        let service = ServiceIdentifier::from_str("plc-gateway.a1").unwrap();
+        assert_eq!(None, service.instance().as_u64());
+        assert_eq!("plc-gateway.a1", service.to_string());
+        assert_eq!(Err(ParseError::EmptyToken), InstanceId::from_str(""));
+    }
+
+    #[test]
+    fn payload_path_depth_limit() {
+        let segments = |n: usize| (0..n).map(|i| i.to_string()).collect::<Vec<_>>();
+        let res = PayloadPath::with_max_depth(segments(3), 3).unwrap();
+        assert_eq!(3, res.depth());
+        assert_eq!(&["0", "1", "2"], res.segments());
+        assert_eq!(
+            Err(ParseError::TooDeep { depth: 4, max: 3 }),
+            PayloadPath::with_max_depth(segments(4), 3)
+        );
+
+        let max = PayloadPath::DEFAULT_MAX_DEPTH;
+        let mut res = PayloadPath::new(segments(max)).unwrap();
+        assert_eq!(
+            Err(ParseError::TooDeep {
+                depth: max + 1,
+                max
+            }),
+            res.push("extra")
+        );
+    }
+
+    #[test]
+    fn payload_path_invalid_segment() {
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            PayloadPath::new(vec!["a".to_string(), String::new()])
+        );
+        let mut res = PayloadPath::default();
+        assert!(res.push("a b").is_err());
+        assert_eq!(0, res.depth());
+    }
+
+    #[test]
+    fn subject_from_string_payload_depth_limit() {
+        let base = "prod.abc.xyz.local.plc-gateway.1.data";
+        let max = PayloadPath::DEFAULT_MAX_DEPTH;
+        let at_limit = format!("{base}{}", ".x".repeat(max));
+        let res = MyceliumSubject::from_str(&at_limit).unwrap();
+        assert_eq!(max, res.payload_identifier.depth());
+
+        let over_limit = format!("{base}{}", ".x".repeat(max + 1));
+        let res = MyceliumSubject::from_str(&over_limit).unwrap_err();
+        assert_eq!(
+            &ParseError::TooDeep {
+                depth: max + 1,
+                max
+            },
+            res.kind()
+        );
+        assert_eq!(Some(7 + max), res.token_index());
+    }
+
+    #[test]
+    fn ownership_group_levels() {
+        let group = OwnershipGroup::from_str("abc.xyz").unwrap();
+        assert_eq!(&["abc", "xyz"], group.levels());
+
+        let mut group = OwnershipGroup::from_str("abc.north.xyz").unwrap();
+        assert_eq!(&["abc", "north", "xyz"], group.levels());
+        assert_eq!("abc", group.enterprise());
+        assert_eq!("xyz", group.op_group());
+        group.set_op_group("uvw").unwrap();
+        assert_eq!("abc.north.uvw", group.to_string());
+
+        assert_eq!(
+            Err(ParseError::InvalidOwnershipGroup),
+            OwnershipGroup::new(vec!["abc".to_string()])
+        );
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            OwnershipGroup::from_str("abc..xyz")
+        );
+    }
+
+    #[test]
+    fn subject_from_string_two_level_ownership() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.data";
+        let res = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(&["abc", "xyz"], res.ownership_group.levels());
+        assert_eq!(GeoLocator::Local, res.geo_locator);
+        assert_eq!(7, res.token_count());
+        assert_eq!(subject_string, res.to_string());
+    }
+
+    #[test]
+    fn subject_from_string_three_level_ownership() {
+        let subject_strings = [
+            "prod.abc.north.xyz.local.plc-gateway.1.data",
+            "prod.abc.north.xyz.global.plc-gateway.1.data.local",
+            "prod.abc.north.xyz.US-CA.south.abc.plc-gateway.1.data.system",
+        ];
+        for subject_string in subject_strings {
+            let res = MyceliumSubject::from_str(subject_string).unwrap();
+            assert_eq!(&["abc", "north", "xyz"], res.ownership_group.levels());
+            assert_eq!("plc-gateway", res.service_identifier.service_name());
+            assert_eq!(subject_string, res.to_string());
+            assert_eq!(subject_string.split('.').count(), res.token_count());
+        }
+
+        #[cfg(not(feature = "custom-payload-types"))]
+        {
+            let res = MyceliumSubject::from_str("prod.abc.north.xyz.local.plc-gateway.1.datas");
+            assert_eq!(Some(7), res.unwrap_err().token_index());
+        }
+    }
+
+    #[test]
+    fn subject_from_string_mistyped_geo_locator() {
+        let res =
+            MyceliumSubject::from_str("prod.abc.xyz.lcoal.plc-gateway.1.data.local.temp.1.data")
+                .unwrap_err();
+        assert_eq!(&ParseError::InvalidIsoCode("lcoal".to_string()), res.kind());
+        assert_eq!(Some(3), res.token_index());
+        assert_eq!(Some(13), res.byte_offset());
+
+        let mut subject =
+            MyceliumSubject::from_str("prod.abc.north.xyz.local.plc-gateway.1.data").unwrap();
+        subject.ownership_group = OwnershipGroup {
+            levels: vec!["abc".to_string(), "north".to_string(), "data".to_string()],
+        };
+        assert_eq!(
+            Err(ParseError::InvalidOwnershipGroup.at(3, 15)),
+            subject.validate()
+        );
+    }
+
+    #[test]
+    fn subject_parent_walks_to_root() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.system.sensor.value")
+                .unwrap();
+        let ancestors: Vec<String> = core::iter::successors(subject.parent(), |s| s.parent())
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "prod.abc.xyz.local.plc-gateway.1.data.system.sensor",
+                "prod.abc.xyz.local.plc-gateway.1.data.system",
+                "prod.abc.xyz.local.plc-gateway.1.data",
+            ],
+            ancestors
+        );
+    }
+
+    #[test]
+    fn subject_parent_of_root() {
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert_eq!(None, subject.parent());
+    }
+
+    #[test]
+    fn subject_wildcards() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.system").unwrap();
+        assert_eq!(
+            "prod.abc.xyz.local.plc-gateway.1.>",
+            subject.service_wildcard().to_string()
+        );
+        assert_eq!("prod.abc.xyz.>", subject.ownership_wildcard().to_string());
+        assert_eq!("prod.>", subject.environment_wildcard().to_string());
+
+        let subject =
+            MyceliumSubject::from_str("dev.abc.north.xyz.US-CA.south.abc.plc-gateway.1.event")
+                .unwrap();
+        assert_eq!(
+            "dev.abc.north.xyz.US-CA.south.abc.plc-gateway.1.>",
+            subject.service_wildcard().to_string()
+        );
+        assert_eq!(
+            "dev.abc.north.xyz.>",
+            subject.ownership_wildcard().to_string()
+        );
+        for pattern in [
+            subject.service_wildcard(),
+            subject.ownership_wildcard(),
+            subject.environment_wildcard(),
+        ] {
+            assert!(subject.matches(&pattern));
+            assert_eq!(
+                pattern,
+                SubjectPattern::from_str(&pattern.to_string()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn subject_from_parts() {
+        let res = MyceliumSubject::from_parts(
+            Environment::Production,
+            OwnershipGroup::from_str("abc.xyz").unwrap(),
+            GeoLocator::from_str("US-CA.south.abc").unwrap(),
+            ServiceIdentifier::from_str("plc-gateway.1").unwrap(),
+            PayloadType::Data,
+            vec!["system".to_string(), "sensor".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            MyceliumSubject::from_str(
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor"
+            )
+            .unwrap(),
+            res
+        );
+
+        let res = MyceliumSubject::from_parts(
+            Environment::Production,
+            OwnershipGroup::from_str("abc.xyz").unwrap(),
+            GeoLocator::Local,
+            ServiceIdentifier::from_str("plc-gateway.1").unwrap(),
+            PayloadType::Data,
+            vec!["system.sensor".to_string()],
+        );
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "system.sensor".to_string(),
+                character: '.'
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn environment_default() {
+        assert_eq!(Environment::Dev, Environment::default());
+    }
+
+    #[test]
+    fn locator_from_string_field_validation() {
+        assert_eq!(Err(ParseError::EmptyToken), Locator::from_str("US-CA..id"));
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            Locator::from_str("US-CA.region.")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "re gion".to_string(),
+                character: ' '
+            }),
+            Locator::from_str("US-CA.re gion.id")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidCharacter {
+                token: "i*d".to_string(),
+                character: '*'
+            }),
+            Locator::from_str("US-CA.region.i*d")
+        );
+        let res = Locator::from_str("US-CA.region.id").unwrap();
+        assert_eq!(["region"], res.op_region());
+        assert_eq!("id", res.op_identifier());
+    }
+
+    #[test]
+    fn subject_from_str_strict_reserved_words() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.data.global";
+        assert!(MyceliumSubject::from_str(subject_string).is_ok());
+        let res = MyceliumSubject::from_str_strict(subject_string).unwrap_err();
+        assert_eq!(&ParseError::ReservedWord("global".to_string()), res.kind());
+        assert_eq!(Some(7), res.token_index());
+        assert_eq!(Some(38), res.byte_offset());
+
+        let res = MyceliumSubject::from_str_strict("prod.abc.xyz.local.plc-gateway.1.data.a.Prod");
+        assert_eq!(
+            &ParseError::ReservedWord("Prod".to_string()),
+            res.unwrap_err().kind()
+        );
+        assert!(
+            MyceliumSubject::from_str_strict("prod.abc.xyz.local.plc-gateway.1.data.production")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn subject_parse_all_errors() {
+        #[cfg(feature = "strict-iso")]
+        let subject_string = "production.abc.xyz.US-AA.south..plc-gateway.1.data";
+        #[cfg(not(feature = "strict-iso"))]
+        let subject_string = "production.abc.xyz.USAA.south..plc-gateway.1.data";
+        let res = MyceliumSubject::parse_all_errors(subject_string).unwrap_err();
+        assert_eq!(3, res.len());
+        assert_eq!(
+            &ParseError::InvalidEnvironment("production".to_string()),
+            res[0].kind()
+        );
+        assert_eq!(Some(0), res[0].token_index());
+        assert!(matches!(res[1].kind(), ParseError::InvalidIsoCode(_)));
+        assert_eq!(Some(3), res[1].token_index());
+        assert_eq!(&ParseError::EmptyToken, res[2].kind());
+        assert_eq!(Some(5), res[2].token_index());
+
+        #[cfg(not(feature = "custom-payload-types"))]
+        {
+            let res = MyceliumSubject::parse_all_errors("prod.abc..local.plc-gateway.1.datas");
+            assert_eq!(2, res.unwrap_err().len());
+        }
+    }
+
+    #[test]
+    fn subject_parse_all_errors_success_and_structure() {
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.data.value";
+        assert_eq!(
+            Ok(MyceliumSubject::from_str(subject_string).unwrap()),
+            MyceliumSubject::parse_all_errors(subject_string)
+        );
+        assert_eq!(
+            Err(vec![ParseError::TooFewTokens {
+                expected: 7,
+                found: 3
+            }]),
+            MyceliumSubject::parse_all_errors("prod.abc.xyz")
+        );
+    }
+
+    #[test]
+    fn subject_from_tokens() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let tokens: Vec<&str> = subject_string.split('.').collect();
+        assert_eq!(
+            MyceliumSubject::from_str(subject_string).unwrap(),
+            MyceliumSubject::from_tokens(tokens).unwrap()
+        );
+
+        let res = MyceliumSubject::from_tokens([
+            "prod",
+            "abc",
+            "xyz",
+            "local",
+            "plc.gateway",
+            "1",
+            "data",
+        ])
+        .unwrap_err();
+        assert_eq!(Some(4), res.token_index());
+        assert_eq!(Some(19), res.byte_offset());
+        assert_eq!(
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1"),
+            MyceliumSubject::from_tokens(["prod", "abc", "xyz", "local", "plc-gateway", "1"])
+        );
+    }
+
+    #[test]
+    fn grammar_arity() {
+        assert_eq!('.', SUBJECT_SEPARATOR);
+        assert_eq!(7, MyceliumSubject::MIN_TOKENS);
+        assert_eq!(9, MyceliumSubject::MIN_LOCATOR_TOKENS);
+        let local = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert_eq!(MyceliumSubject::MIN_TOKENS, local.token_count());
+        let locator =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data").unwrap();
+        assert_eq!(MyceliumSubject::MIN_LOCATOR_TOKENS, locator.token_count());
+    }
+
+    #[test]
+    fn dotted_tokens_rejected() {
+        let dotted = ParseError::InvalidCharacter {
+            token: "v1.2".to_string(),
+            character: '.',
+        };
+        let mut service = ServiceIdentifier::from_str("plc-gateway.1").unwrap();
+        assert_eq!(Err(dotted.clone()), service.set_service_name("v1.2"));
+        let mut group = OwnershipGroup::from_str("abc.xyz").unwrap();
+        assert_eq!(Err(dotted.clone()), group.set_op_group("v1.2"));
+        assert_eq!(Err(dotted.clone()), InstanceId::from_str("v1.2"));
+        assert_eq!(Err(dotted), PayloadPath::new(vec!["v1.2".to_string()]));
+        assert_eq!(
+            Err(ParseError::InvalidServiceIdentifier),
+            ServiceIdentifier::from_str("v1.2.1")
+        );
+
+        let res = MyceliumSubject::from_str("prod.abc.xyz.local.v1-2.1.data.v1-2").unwrap();
+        assert_eq!("v1-2", res.service_identifier.service_name());
+        assert_eq!(res, MyceliumSubject::from_str(&res.to_string()).unwrap());
+    }
+
+    #[test]
+    fn subject_stable_hash() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        assert_eq!(0x7da3_c6f2_e274_757a, subject.stable_hash());
+        let lowercase = MyceliumSubject::from_str(
+            "PROD.abc.xyz.us-ca.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        assert_eq!(subject.stable_hash(), lowercase.stable_hash());
+        assert_ne!(
+            subject.stable_hash(),
+            subject.parent().unwrap().stable_hash()
+        );
+    }
+
+    #[test]
+    fn subject_prefixes() {
+        let cases = [
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data",
+                "prod.abc.xyz",
+                "prod.abc.xyz.local",
+            ),
+            (
+                "dev.abc.north.xyz.global.plc-gateway.1.event.alarm",
+                "dev.abc.north.xyz",
+                "dev.abc.north.xyz.global",
+            ),
+            (
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+                "prod.abc.xyz",
+                "prod.abc.xyz.US-CA.south.abc",
+            ),
+        ];
+        for (subject_string, ownership_prefix, geo_prefix) in cases {
+            let subject = MyceliumSubject::from_str(subject_string).unwrap();
+            assert_eq!(ownership_prefix, subject.ownership_prefix());
+            assert_eq!(geo_prefix, subject.geo_prefix());
+        }
+    }
+
+    #[test]
+    fn subject_into_iter() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+            "dev.abc.north.xyz.global.plc-gateway.1.event",
+            "prod.abc.xyz.local.plc-gateway.1.heartbeat.a",
+        ];
+        for subject_string in subject_strings {
+            let subject = MyceliumSubject::from_str(subject_string).unwrap();
+            let borrowed: Vec<String> = (&subject).into_iter().collect();
+            let owned: Vec<String> = subject.into_iter().collect();
+            assert_eq!(borrowed, owned);
+            assert_eq!(subject_string, owned.join("."));
+        }
+    }
+
+    #[test]
+    fn subject_into_string() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.north.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        let borrowed = String::from(&subject);
+        assert_eq!(subject.to_string(), borrowed);
+        assert_eq!(borrowed.len(), borrowed.capacity());
+        let owned: String = subject.into();
+        assert_eq!(borrowed, owned);
+        assert_eq!(owned.len(), owned.capacity());
+
+        let subject = MyceliumSubject::from_str_no_geo("prod.abc.xyz.plc-gateway.1.data").unwrap();
+        let borrowed = String::from(&subject);
+        assert_eq!(subject.to_string(), borrowed);
+        assert_eq!(borrowed, String::from(subject));
+    }
+
+    #[test]
+    fn subject_validate() {
+        let mut subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        assert_eq!(Ok(()), subject.validate());
+
+        subject.ownership_group = OwnershipGroup {
+            levels: vec![String::new(), "xyz".to_string()],
+        };
+        let res = subject.validate().unwrap_err();
+        assert_eq!(&ParseError::EmptyToken, res.kind());
+        assert_eq!(Some(1), res.token_index());
+        assert_eq!(Some(5), res.byte_offset());
+
+        subject.ownership_group = OwnershipGroup {
+            levels: vec!["abc".to_string()],
+        };
+        assert_eq!(Err(ParseError::InvalidOwnershipGroup), subject.validate());
+
+        subject.ownership_group =
+            serde_json::from_str(r#"{"levels":["abc","xyz","local"]}"#).unwrap();
+        assert_eq!(Some(3), subject.validate().unwrap_err().token_index());
+    }
+
+    #[test]
+    fn ownership_group_serde() {
+        let group = OwnershipGroup::new(vec!["abc".into(), "north".into(), "xyz".into()]).unwrap();
+        let json = serde_json::to_string(&group).unwrap();
+        assert_eq!(r#"{"levels":["abc","north","xyz"]}"#, json);
+        assert_eq!(group, serde_json::from_str(&json).unwrap());
+
+        let legacy: OwnershipGroup =
+            serde_json::from_str(r#"{"enterprise":"abc","op_group":"xyz"}"#).unwrap();
+        assert_eq!(OwnershipGroup::from_parts("abc", "xyz").unwrap(), legacy);
+
+        for invalid in [
+            r#"{"levels":[]}"#,
+            r#"{"levels":["abc"]}"#,
+            r#"{"levels":["","xyz"]}"#,
+            r#"{"enterprise":"abc","op_group":"x.y"}"#,
+        ] {
+            assert!(serde_json::from_str::<OwnershipGroup>(invalid).is_err());
+        }
+
+        // A subject record as written before multi-level ownership groups.
+        let record = r#"{
+            "environment": "prod",
+            "ownership_group": {"enterprise": "abc", "op_group": "xyz"},
+            "geo_locator": {"Locator": {"iso_3166_2": "US-CA", "op_region": "south", "op_identifier": "abc"}},
+            "service_identifier": {"service_name": "plc-gateway", "instance_id": "1"},
+            "payload_type": "data",
+            "payload_identifier": ["system", "sensor"]
+        }"#;
+        assert_eq!(
+            MyceliumSubject::from_str(
+                "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor"
+            )
+            .unwrap(),
+            serde_json::from_str::<MyceliumSubject>(record).unwrap()
+        );
+    }
+
+    #[test]
+    fn service_identifier_serde() {
+        let service = ServiceIdentifier::new("plc-gateway", "1").unwrap();
+        let json = serde_json::to_string(&service).unwrap();
+        assert_eq!(r#"{"service_name":"plc-gateway","instance_id":"1"}"#, json);
+        assert_eq!(service, serde_json::from_str(&json).unwrap());
+
+        for invalid in [
+            r#"{"service_name":"a.b","instance_id":"1"}"#,
+            r#"{"service_name":"","instance_id":"1"}"#,
+            r#"{"service_name":"plc-gateway","instance_id":"1.2"}"#,
+        ] {
+            assert!(serde_json::from_str::<ServiceIdentifier>(invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn instance_id_serde() {
+        let id = InstanceId::from_str("1").unwrap();
+        assert_eq!("\"1\"", serde_json::to_string(&id).unwrap());
+        assert_eq!(id, serde_json::from_str("\"1\"").unwrap());
+        assert!(serde_json::from_str::<InstanceId>("\"\"").is_err());
+        assert!(serde_json::from_str::<InstanceId>("\"1.2\"").is_err());
+
+        let record = r#"{"service_name":"plc-gateway","instance_id":""}"#;
+        assert!(serde_json::from_str::<ServiceIdentifier>(record).is_err());
+    }
+
+    #[test]
+    fn payload_path_serde() {
+        let path = PayloadPath::new(vec!["system".into(), "sensor".into()]).unwrap();
+        let json = serde_json::to_string(&path).unwrap();
+        assert_eq!(r#"["system","sensor"]"#, json);
+        assert_eq!(path, serde_json::from_str(&json).unwrap());
+
+        assert!(serde_json::from_str::<PayloadPath>(r#"[""]"#).is_err());
+        assert!(serde_json::from_str::<PayloadPath>(r#"["a.b"]"#).is_err());
+        let deep = serde_json::to_string(&vec!["a"; PayloadPath::DEFAULT_MAX_DEPTH + 1]).unwrap();
+        assert!(serde_json::from_str::<PayloadPath>(&deep).is_err());
+    }
+
+    #[test]
+    fn subject_validate_components() {
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        let mut custom = subject.clone();
+        custom.payload_type = PayloadType::Custom("a.b".to_string());
+        assert_eq!(Some(6), custom.validate().unwrap_err().token_index());
+
+        #[cfg(feature = "strict-iso")]
+        {
+            let mut locator = subject.clone();
+            locator.geo_locator = GeoLocator::Locator(Locator {
+                iso_3166_2: "US-AA".to_string(),
+                op_region: vec!["south".to_string()],
+                op_identifier: "abc".to_string(),
+            });
+            assert_eq!(
+                &ParseError::InvalidIsoCode("US-AA".to_string()),
+                locator.validate().unwrap_err().kind()
+            );
+        }
+    }
+
+    #[test]
+    fn subject_custom_separator_round_trip() {
+        let subject_string =
+            "prod/abc/xyz/US-CA/south/abc/plc-gateway/1/data/system/sub-system/sensor/value";
+        let subject = MyceliumSubject::from_str_with_separator(subject_string, '/').unwrap();
+        assert_eq!(subject_string.replace('/', "."), subject.to_string());
+        assert_eq!(
+            subject_string,
+            subject.display_with_separator('/').to_string()
+        );
+        assert_eq!(
+            subject,
+            MyceliumSubject::from_str_with_separator(&subject.to_string(), '.').unwrap()
+        );
+
+        let res =
+            MyceliumSubject::from_str_with_separator("prod/abc/xyz/local/plc.gateway/1/data", '/')
+                .unwrap_err();
+        assert_eq!(Some(4), res.token_index());
+        assert_eq!(Some(19), res.byte_offset());
+    }
+
+    #[test]
+    fn environment_serde_as_token() {
+        assert_eq!(
+            "\"prod\"",
+            serde_json::to_string(&Environment::Production).unwrap()
+        );
+        assert_eq!(
+            Environment::Staging,
+            serde_json::from_str::<Environment>("\"staging\"").unwrap()
+        );
+        assert!(serde_json::from_str::<Environment>("\"Production\"").is_err());
+    }
+
+    #[test]
+    fn payload_type_serde_as_token() {
+        assert_eq!(
+            "\"heartbeat\"",
+            serde_json::to_string(&PayloadType::Heartbeat).unwrap()
+        );
+        assert_eq!(
+            "\"custom\"",
+            serde_json::to_string(&PayloadType::Custom("custom".to_string())).unwrap()
+        );
+        assert_eq!(
+            PayloadType::Data,
+            serde_json::from_str::<PayloadType>("\"data\"").unwrap()
+        );
+        #[cfg(not(feature = "custom-payload-types"))]
+        assert!(serde_json::from_str::<PayloadType>("\"telemetry\"").is_err());
+    }
+
+    #[test]
+    fn subject_is_prefix_of() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        let prefix =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system")
+                .unwrap();
+        assert!(prefix.is_prefix_of(&subject));
+        assert!(!subject.is_prefix_of(&prefix));
+        assert!(subject.is_prefix_of(&subject));
+
+        let other =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.other")
+                .unwrap();
+        assert!(!other.is_prefix_of(&subject));
+        let local = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert!(!local.is_prefix_of(&subject));
+    }
+
+    #[test]
+    fn subject_write_to() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        let mut buf = String::new();
+        subject.write_to(&mut buf);
+        assert_eq!(subject.to_string(), buf);
+
+        buf.push(' ');
+        subject.write_to(&mut buf);
+        assert_eq!(format!("{subject} {subject}"), buf);
+    }
+
+    #[test]
+    fn from_str_with_instance_id_format() {
+        let subject_string = "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.sensor";
+        assert!(
+            MyceliumSubject::from_str_with_instance_id_format(
+                subject_string,
+                InstanceIdFormat::Numeric
+            )
+            .is_ok()
+        );
+        let res = MyceliumSubject::from_str_with_instance_id_format(
+            subject_string,
+            InstanceIdFormat::Uuid,
+        )
+        .unwrap_err();
+        assert_eq!(
+            &ParseError::InvalidInstanceId {
+                id: "1".to_string(),
+                expected: InstanceIdFormat::Uuid
+            },
+            res.kind()
+        );
+        assert_eq!(Some(7), res.token_index());
+        assert_eq!(Some(41), res.byte_offset());
+        assert_eq!(
+            "instance id \"1\" is not a UUID at token 7 (byte offset 41)",
+            res.to_string()
+        );
+    }
+
+    #[test]
+    fn locator_iso_code_normalized() {
+        let upper = Locator::from_str("US-CA.south.abc").unwrap();
+        assert_eq!(upper, Locator::from_str("us-ca.south.abc").unwrap());
+
+        let mut set = Locator::from_str("US-TX.south.abc").unwrap();
+        set.set_iso_3166_2("us-ca").unwrap();
+        assert_eq!(upper, set);
+
+        let json = r#"{"iso_3166_2":"us-ca","op_region":["south"],"op_identifier":"abc"}"#;
+        assert_eq!(upper, serde_json::from_str::<Locator>(json).unwrap());
+        assert_eq!(
+            json.replace("us-ca", "US-CA"),
+            serde_json::to_string(&upper).unwrap()
+        );
+        let legacy = r#"{"iso_3166_2":"us-ca","op_region":"south","op_identifier":"abc"}"#;
+        assert_eq!(upper, serde_json::from_str::<Locator>(legacy).unwrap());
+        for invalid in [
+            r#"{"iso_3166_2":"USCA","op_region":["south"],"op_identifier":"abc"}"#,
+            r#"{"iso_3166_2":"US-CA","op_region":[],"op_identifier":"abc"}"#,
+            r#"{"iso_3166_2":"US-CA","op_region":["so.uth"],"op_identifier":"abc"}"#,
+            r#"{"iso_3166_2":"US-CA","op_region":["south"],"op_identifier":""}"#,
+        ] {
+            assert!(serde_json::from_str::<Locator>(invalid).is_err());
+        }
+        #[cfg(feature = "strict-iso")]
+        assert!(
+            serde_json::from_str::<Locator>(
+                r#"{"iso_3166_2":"ZZ-QQ","op_region":["south"],"op_identifier":"abc"}"#
+            )
+            .is_err()
+        );
+
+        let subject = MyceliumSubjectRef::parse("prod.abc.xyz.us-ca.south.abc.plc-gateway.1.data")
+            .unwrap()
+            .to_owned();
+        assert_eq!(GeoLocator::Locator(upper), subject.geo_locator);
+    }
+
+    #[test]
+    fn subject_from_utf8() {
+        let subject_string = "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.sensor";
+        assert_eq!(
+            MyceliumSubject::from_str(subject_string).unwrap(),
+            MyceliumSubject::from_utf8(subject_string.as_bytes()).unwrap()
+        );
+
+        let res = MyceliumSubject::from_utf8(b"prod.abc.xyz.local.plc\xff.1.data").unwrap_err();
+        match res {
+            ParseError::InvalidUtf8(error) => assert_eq!(22, error.valid_up_to()),
+            error => panic!("unexpected error {error}"),
+        }
+        assert!(MyceliumSubject::from_utf8(b"prod.abc").is_err());
+    }
+
+    #[test]
+    fn subject_payload_path() {
+        let cases = [
+            ("prod.abc.xyz.local.plc-gateway.1.data", None),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data.sensor",
+                Some("sensor"),
+            ),
+            (
+                "prod.abc.xyz.local.plc-gateway.1.data.system.sub-system.sensor.value",
+                Some("system.sub-system.sensor.value"),
+            ),
+        ];
+        for (subject_string, payload_path) in cases {
+            let subject = MyceliumSubject::from_str(subject_string).unwrap();
+            assert_eq!(payload_path.map(String::from), subject.payload_path());
+            let segments: Vec<&str> = payload_path
+                .map(|path| path.split('.').collect())
+                .unwrap_or_default();
+            assert_eq!(segments, subject.payload_segments());
+        }
+    }
+
+    #[test]
+    fn subject_canonicalize() {
+        let mut messy = MyceliumSubject::from_str(
+            "PROD.abc.xyz.US-CA.south.abc.plc-gateway.1.data.System.sensor",
+        )
+        .unwrap();
+        messy.geo_locator = GeoLocator::Locator(Locator {
+            iso_3166_2: "us-ca".to_string(),
+            op_region: vec!["south".to_string()],
+            op_identifier: "abc".to_string(),
+        });
+        messy.payload_type = PayloadType::Custom("Data".to_string());
+        assert_eq!(
+            "prod.abc.xyz.us-ca.south.abc.plc-gateway.1.Data.System.sensor",
+            messy.to_string()
+        );
+
+        let canonical = messy.canonicalize();
+        assert_eq!(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.System.sensor",
+            canonical.to_string()
+        );
+        assert_eq!(PayloadType::Data, canonical.payload_type);
+        assert_eq!(canonical, canonical.canonicalize());
+        assert_eq!(
+            MyceliumSubject::from_str(&canonical.to_string()).unwrap(),
+            canonical
+        );
+    }
+
+    #[test]
+    fn subject_display_alternate() {
+        let subject_string =
+            "prod.abc.north.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(subject_string, format!("{subject}"));
+        assert_eq!(
+            "environment: prod\n\
+             enterprise: abc\n\
+             level: north\n\
+             op_group: xyz\n\
+             geo: US-CA.south.abc\n\
+             service_name: plc-gateway\n\
+             instance_id: 1\n\
+             payload_type: data\n\
+             payload_path: system.sub-system",
+            format!("{subject:#}")
+        );
+
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert!(format!("{subject:#}").ends_with("payload_type: data"));
+    }
+
+    #[test]
+    fn subject_to_field_map() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        )
+        .unwrap();
+        let expected = BTreeMap::from([
+            ("environment", "prod".to_string()),
+            ("enterprise", "abc".to_string()),
+            ("op_group", "xyz".to_string()),
+            ("geo", "US-CA.south.abc".to_string()),
+            ("service_name", "plc-gateway".to_string()),
+            ("instance_id", "1".to_string()),
+            ("payload_type", "data".to_string()),
+            ("payload_path", "system.sub-system.sensor.value".to_string()),
+        ]);
+        assert_eq!(expected, subject.to_field_map());
+
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert!(!subject.to_field_map().contains_key("payload_path"));
+    }
+
+    #[test]
+    fn subject_from_str_trimmed() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        for pasted in [
+            format!(".{subject_string}"),
+            format!("{subject_string}."),
+            format!(".{subject_string}."),
+            subject_string.to_string(),
+        ] {
+            assert_eq!(subject, MyceliumSubject::from_str_trimmed(&pasted).unwrap());
+        }
+        assert!(MyceliumSubject::from_str(&format!(".{subject_string}")).is_err());
+        assert!(MyceliumSubject::from_str(&format!("{subject_string}.")).is_err());
+        assert!(MyceliumSubject::from_str_trimmed(&format!("..{subject_string}")).is_err());
+    }
+
+    #[test]
+    fn locator_new() {
+        let locator = Locator::new("us-ca", "south", "abc").unwrap();
+        assert_eq!(Locator::from_str("US-CA.south.abc").unwrap(), locator);
+        assert_eq!(
+            GeoLocator::Locator(locator),
+            GeoLocator::locator("US-CA", "south", "abc").unwrap()
+        );
+        assert!(Locator::new("USCA", "south", "abc").is_err());
+        assert!(GeoLocator::locator("US-CA", "south.east", "abc").is_err());
+        assert!(GeoLocator::locator("US-CA", "south", "").is_err());
+        #[cfg(feature = "strict-iso")]
+        assert_eq!(
+            Err(ParseError::InvalidIsoCode("US-AA".to_string())),
+            GeoLocator::locator("US-AA", "south", "abc")
+        );
+    }
+
+    #[test]
+    fn ownership_group_from_parts() {
+        assert_eq!(
+            OwnershipGroup::from_str("abc.xyz").unwrap(),
+            OwnershipGroup::from_parts("abc", "xyz").unwrap()
+        );
+        assert_eq!(
+            Err(ParseError::EmptyToken),
+            OwnershipGroup::from_parts("", "xyz")
+        );
+        assert!(OwnershipGroup::from_parts("abc.north", "xyz").is_err());
+    }
+
+    #[test]
+    fn service_identifier_new() {
+        assert_eq!(
+            ServiceIdentifier::from_str("plc-gateway.1").unwrap(),
+            ServiceIdentifier::new("plc-gateway", "1").unwrap()
+        );
+        assert_eq!(Err(ParseError::EmptyToken), ServiceIdentifier::new("", "1"));
+        assert!(ServiceIdentifier::new("plc-gateway", "1.2").is_err());
+    }
+
+    #[test]
+    fn payload_type_case_insensitive() {
+        let cases = [
+            ("DATA", PayloadType::Data),
+            ("Heartbeat", PayloadType::Heartbeat),
+            ("command", PayloadType::Command),
+            ("Custom", PayloadType::Custom("custom".to_string())),
+        ];
+        for (token, payload_type) in cases {
+            let res = PayloadType::from_str(token).unwrap();
+            assert_eq!(payload_type, res);
+            assert_eq!(token.to_ascii_lowercase(), res.to_string());
+        }
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.DATA").unwrap();
+        assert_eq!("prod.abc.xyz.local.plc-gateway.1.data", subject.to_string());
+
+        #[cfg(feature = "custom-payload-types")]
+        assert_eq!(
+            PayloadType::Custom("Telemetry".to_string()),
+            PayloadType::from_str("Telemetry").unwrap()
+        );
+    }
+
+    #[test]
+    fn subject_hierarchical_region() {
+        let subject_string = "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.sensor";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        let GeoLocator::Locator(locator) = &subject.geo_locator else {
+            panic!("expected a locator");
+        };
+        assert_eq!(["south"], locator.op_region());
+        assert_eq!("abc", locator.op_identifier());
+
+        let subject_string = "prod.abc.xyz.US-CA.north.bay-area.abc.plc-gateway.1.data.sensor";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(
+            GeoLocator::Locator(
+                Locator::with_region_segments(
+                    "US-CA",
+                    vec!["north".to_string(), "bay-area".to_string()],
+                    "abc"
+                )
+                .unwrap()
+            ),
+            subject.geo_locator
+        );
+        assert_eq!("plc-gateway", subject.service_identifier.service_name());
+        assert_eq!(["sensor"], subject.payload_segments());
+        assert_eq!(subject_string, subject.to_string());
+        assert_eq!(11, subject.token_count());
+        assert_eq!(Ok(()), subject.validate());
+        assert_eq!(
+            subject,
+            MyceliumSubject::from_tokens(subject.clone()).unwrap()
+        );
+
+        // Without its instance id, the subject would read as a single-segment region with
+        // shifted identifier and service. A format for instance ids turns that into an error.
+        let res = MyceliumSubject::from_str_with_instance_id_format(
+            "prod.abc.xyz.US-CA.north.bay-area.abc.plc-gateway.data",
+            InstanceIdFormat::Numeric,
+        )
+        .unwrap_err();
+        assert_eq!(
+            &ParseError::InvalidInstanceId {
+                id: "plc-gateway".to_string(),
+                expected: InstanceIdFormat::Numeric
+            },
+            res.kind()
+        );
+        assert_eq!(Some(7), res.token_index());
+    }
+
+    #[test]
+    fn subject_validate_ambiguous_region() {
+        let mut subject =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.north.abc.plc-gateway.1.data")
+                .unwrap();
+        assert_eq!(Ok(()), subject.validate());
+        // `US-CA.south.north.abc.plc-gateway.event.data` reads back with a single region
+        // segment and `event` as the payload type.
+        subject.service_identifier.set_instance_id("event").unwrap();
+        let res = subject.validate().unwrap_err();
+        assert_eq!(&ParseError::InvalidLocator, res.kind());
+        assert_eq!(Some(3), res.token_index());
+
+        assert!(Locator::with_region_segments("US-CA", vec![], "abc").is_err());
+        assert!(Locator::from_str("US-CA.abc").is_err());
+    }
+
+    #[test]
+    fn from_string_fail_too_many_tokens() {
+        let mut subject_string = "prod.abc.xyz.local.plc-gateway.1.data".to_string();
+        subject_string.push_str(&".a".repeat(50_000));
+        let res = MyceliumSubject::from_str(&subject_string).unwrap_err();
+        assert_eq!(
+            &ParseError::TooManyTokens {
+                expected: MyceliumSubject::DEFAULT_MAX_TOKENS,
+                found: MyceliumSubject::DEFAULT_MAX_TOKENS + 1
+            },
+            res.kind()
+        );
+        assert_eq!(Some(MyceliumSubject::DEFAULT_MAX_TOKENS), res.token_index());
+
+        let subject_string = "prod.abc.xyz.local.plc-gateway.1.data.sensor";
+        assert!(MyceliumSubject::from_str_with_max_tokens(subject_string, 8).is_ok());
+        let res = MyceliumSubject::from_str_with_max_tokens(subject_string, 7).unwrap_err();
+        assert_eq!(
+            &ParseError::TooManyTokens {
+                expected: 7,
+                found: 8
+            },
+            res.kind()
+        );
+    }
+
+    #[test]
+    fn subject_with_component() {
+        let subject = MyceliumSubject::from_str(
+            "dev.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        let promoted = subject.with_environment(Environment::Production);
+        assert_eq!(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+            promoted.to_string()
+        );
+        assert!(promoted.tokens().skip(1).eq(subject.tokens().skip(1)));
+        assert_eq!(Environment::Dev, subject.environment);
+
+        assert_eq!(
+            "dev.abc.xyz.US-CA.south.abc.plc-gateway.1.event.system.sensor",
+            subject.with_payload_type(PayloadType::Event).to_string()
+        );
+        assert_eq!(
+            "dev.abc.xyz.US-CA.south.abc.plc-gateway.2.data.system.sensor",
+            subject
+                .with_service_identifier(ServiceIdentifier::new("plc-gateway", "2").unwrap())
+                .to_string()
+        );
+    }
 }
@@ -0,0 +1,112 @@
+use crate::{MyceliumSubject, ParseError};
+use core::str::FromStr;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::io::{self, BufRead};
+
+/// Parses one subject per line of `reader`, yielding the 1-based line number with each result.
+///
+/// Blank lines are skipped. Lines are parsed as-is, so surrounding whitespace is an error.
+/// A read error, including a line that is not valid UTF-8, is yielded as an `Err` item and
+/// ends the iteration, so a truncated read never looks like a complete one.
+pub fn parse_lines<R: BufRead>(
+    reader: R,
+) -> impl Iterator<Item = io::Result<(usize, Result<MyceliumSubject, ParseError>)>> {
+    let mut failed = false;
+    reader
+        .lines()
+        .enumerate()
+        .map_while(move |(index, line)| {
+            if failed {
+                return None;
+            }
+            Some(match line {
+                Ok(line) if line.trim().is_empty() => None,
+                Ok(line) => Some(Ok((index + 1, MyceliumSubject::from_str(&line)))),
+                Err(error) => {
+                    failed = true;
+                    Some(Err(error))
+                }
+            })
+        })
+        .flatten()
+}
+
+/// Parses every subject in `lines` on the rayon thread pool, returning the results in the
+/// same order as `lines`.
+///
+/// Unlike [`parse_lines`] nothing is skipped, so result `i` is always for `lines[i]`.
+#[cfg(feature = "rayon")]
+pub fn parse_many_parallel(lines: &[&str]) -> Vec<Result<MyceliumSubject, ParseError>> {
+    lines
+        .par_iter()
+        .map(|line| MyceliumSubject::from_str(line))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_lines_mixed() {
+        let input = "prod.abc.xyz.local.plc-gateway.1.data\n\
+                     \n\
+                     production.abc.xyz.local.plc-gateway.1.data\n\
+                     dev.abc.xyz.global.plc-gateway.1.event.alarm\r\n\
+                     \x20\x20\n\
+                     prod.abc.xyz\n";
+        let res: Vec<_> = parse_lines(input.as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        let lines: Vec<usize> = res.iter().map(|(line, _)| *line).collect();
+        assert_eq!(vec![1, 3, 4, 6], lines);
+        assert!(res[0].1.is_ok());
+        assert_eq!(
+            &ParseError::InvalidEnvironment("production".to_string()),
+            res[1].1.as_ref().unwrap_err().kind()
+        );
+        assert_eq!(
+            "dev.abc.xyz.global.plc-gateway.1.event.alarm",
+            res[2].1.as_ref().unwrap().to_string()
+        );
+        assert!(res[3].1.is_err());
+    }
+
+    #[test]
+    fn parse_lines_read_error() {
+        let input: &[u8] = b"prod.abc.xyz.local.plc-gateway.1.data\n\
+                             prod.abc.xyz.local.plc-\xff.1.data\n\
+                             prod.abc.xyz.local.plc-gateway.2.data\n";
+        let mut res = parse_lines(input);
+        let (line, subject) = res.next().unwrap().unwrap();
+        assert_eq!(1, line);
+        assert!(subject.is_ok());
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            res.next().unwrap().unwrap_err().kind()
+        );
+        assert!(res.next().is_none());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parse_many_parallel_matches_sequential() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+            "production.abc.xyz.local.plc-gateway.1.data",
+            "dev.abc.xyz.global.scheduler.2.command.restart",
+            "",
+            "prod.abc.xyz.XX-ZZ.south.abc.plc-gateway.1.data",
+            "qa.abc.north.xyz.unknown.plc-gateway.1.heartbeat",
+            "prod.abc.xyz",
+        ];
+        let lines: Vec<&str> = subject_strings.iter().cycle().take(1000).copied().collect();
+        let sequential: Vec<_> = lines
+            .iter()
+            .map(|line| MyceliumSubject::from_str(line))
+            .collect();
+        assert_eq!(sequential, parse_many_parallel(&lines));
+        assert!(parse_many_parallel(&[]).is_empty());
+    }
+}
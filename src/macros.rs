@@ -0,0 +1,41 @@
+/// Parses a subject literal, panicking with the parse error if it is invalid.
+///
+/// The literal is checked when the expression runs, not at compile time, so embedded
+/// subjects should be covered by a test that evaluates them.
+///
+/// ```
+/// use mycelium_uns_rs::mycelium_subject;
+///
+/// let subject = mycelium_subject!("prod.abc.xyz.local.plc-gateway.1.data");
+/// assert_eq!("plc-gateway", subject.service_identifier.service_name());
+/// ```
+#[macro_export]
+macro_rules! mycelium_subject {
+    ($subject:literal) => {
+        <$crate::MyceliumSubject as ::core::str::FromStr>::from_str($subject)
+            .unwrap_or_else(|error| panic!("invalid Mycelium subject {:?}: {}", $subject, error))
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::MyceliumSubject;
+    use core::str::FromStr;
+
+    #[test]
+    fn macro_valid_literal() {
+        let subject_string = "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.value";
+        assert_eq!(
+            MyceliumSubject::from_str(subject_string).unwrap(),
+            mycelium_subject!("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.value")
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "invalid Mycelium subject \"production.abc.xyz.local.plc-gateway.1.data\": invalid environment"
+    )]
+    fn macro_invalid_literal() {
+        mycelium_subject!("production.abc.xyz.local.plc-gateway.1.data");
+    }
+}
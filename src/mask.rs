@@ -0,0 +1,112 @@
+use crate::{Environment, MyceliumSubject, PayloadType, SUBJECT_SEPARATOR, ServiceIdentifier};
+use alloc::string::String;
+use core::ops::{BitOr, BitOrAssign};
+
+/// A set of subject components, combined with `|`, for
+/// [`MyceliumSubject::display_components`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub struct ComponentMask(u8);
+
+impl ComponentMask {
+    pub const ENVIRONMENT: Self = ComponentMask(1 << 0);
+    /// Every ownership level.
+    pub const OWNERSHIP: Self = ComponentMask(1 << 1);
+    /// Every geo-locator token.
+    pub const GEO: Self = ComponentMask(1 << 2);
+    /// The service name and instance id.
+    pub const SERVICE: Self = ComponentMask(1 << 3);
+    pub const PAYLOAD_TYPE: Self = ComponentMask(1 << 4);
+    /// Every payload identifier.
+    pub const PAYLOAD: Self = ComponentMask(1 << 5);
+    pub const ALL: Self = ComponentMask((1 << 6) - 1);
+
+    pub const fn empty() -> Self {
+        ComponentMask(0)
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for ComponentMask {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        ComponentMask(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for ComponentMask {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl MyceliumSubject {
+    /// The tokens of the components in `mask`, joined by `.`.
+    ///
+    /// This is meant for logs: unless `mask` is [`ComponentMask::ALL`], the result is usually
+    /// not a valid subject.
+    pub fn display_components(&self, mask: ComponentMask) -> String {
+        let components = [
+            (ComponentMask::ENVIRONMENT, Environment::TOKENS),
+            (
+                ComponentMask::OWNERSHIP,
+                self.ownership_group.levels().len(),
+            ),
+            (ComponentMask::GEO, self.geo_locator.token_count()),
+            (ComponentMask::SERVICE, ServiceIdentifier::TOKENS),
+            (ComponentMask::PAYLOAD_TYPE, PayloadType::TOKENS),
+            (ComponentMask::PAYLOAD, self.payload_identifier.depth()),
+        ];
+        let mut tokens = self.tokens();
+        let mut s = String::new();
+        for (component, count) in components {
+            for token in tokens.by_ref().take(count) {
+                if mask.contains(component) {
+                    if !s.is_empty() {
+                        s.push(SUBJECT_SEPARATOR);
+                    }
+                    s.push_str(&token);
+                }
+            }
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn display_components_masked() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let subject = MyceliumSubject::from_str(subject_string).unwrap();
+        assert_eq!(
+            "prod.plc-gateway.1",
+            subject.display_components(ComponentMask::ENVIRONMENT | ComponentMask::SERVICE)
+        );
+        assert_eq!(
+            "US-CA.south.abc.data",
+            subject.display_components(ComponentMask::GEO | ComponentMask::PAYLOAD_TYPE)
+        );
+        assert_eq!(
+            "system.sub-system.sensor.value",
+            subject.display_components(ComponentMask::PAYLOAD)
+        );
+        assert_eq!(
+            subject_string,
+            subject.display_components(ComponentMask::ALL)
+        );
+        assert_eq!("", subject.display_components(ComponentMask::empty()));
+
+        let mut mask = ComponentMask::empty();
+        mask |= ComponentMask::OWNERSHIP;
+        assert!(mask.contains(ComponentMask::OWNERSHIP));
+        assert!(!mask.contains(ComponentMask::GEO));
+        assert!(ComponentMask::ALL.contains(mask | ComponentMask::PAYLOAD));
+    }
+}
@@ -0,0 +1,170 @@
+//! Bridging between Mycelium subjects and MQTT topics.
+//!
+//! MQTT separates levels with `/` and uses `+` and `#` as wildcards, where NATS uses `.`,
+//! `*` and `>`. Tokens containing any character that has a meaning on the other side of the
+//! bridge cannot be represented there and are rejected.
+
+use crate::{MyceliumSubject, ParseError, SubjectPattern, Token};
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+/// Rejects tokens that would change meaning once placed in an MQTT topic.
+fn validate_mqtt_token(token: &str) -> Result<(), ParseError> {
+    match token.chars().find(|c| matches!(c, '/' | '+' | '#')) {
+        Some(character) => Err(ParseError::InvalidCharacter {
+            token: token.to_string(),
+            character,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Joins `levels` with `/`, checking the ones flagged as literals with [`validate_mqtt_token`].
+fn join_mqtt(levels: impl Iterator<Item = (impl AsRef<str>, bool)>) -> Result<String, ParseError> {
+    let mut topic = String::new();
+    for (index, (level, literal)) in levels.enumerate() {
+        let level = level.as_ref();
+        if index > 0 {
+            topic.push('/');
+        }
+        if literal {
+            validate_mqtt_token(level).map_err(|error| error.at(index, topic.len()))?;
+        }
+        topic.push_str(level);
+    }
+    Ok(topic)
+}
+
+/// Converts MQTT levels back to a dotted NATS string, mapping each level with `level`.
+fn split_mqtt(
+    topic: &str,
+    level: impl Fn(&str) -> Result<&str, ParseError>,
+) -> Result<String, ParseError> {
+    let mut subject = String::with_capacity(topic.len());
+    for (index, part) in topic.split('/').enumerate() {
+        if index > 0 {
+            subject.push('.');
+        }
+        let part = level(part).map_err(|error| error.at(index, subject.len()))?;
+        subject.push_str(part);
+    }
+    Ok(subject)
+}
+
+/// Rejects `.` in an MQTT level, since it would split into several NATS tokens.
+fn reject_dot(level: &str) -> Result<&str, ParseError> {
+    if level.contains('.') {
+        return Err(ParseError::InvalidCharacter {
+            token: level.to_string(),
+            character: '.',
+        });
+    }
+    Ok(level)
+}
+
+impl MyceliumSubject {
+    /// The subject as an MQTT topic, with `/` between tokens.
+    pub fn to_mqtt_topic(&self) -> Result<String, ParseError> {
+        join_mqtt(self.tokens().map(|token| (token, true)))
+    }
+
+    /// Parses an MQTT topic produced by [`Self::to_mqtt_topic`].
+    pub fn from_mqtt_topic(topic: &str) -> Result<Self, ParseError> {
+        let subject = split_mqtt(topic, |level| {
+            validate_mqtt_token(level)?;
+            reject_dot(level)
+        })?;
+        MyceliumSubject::from_str(&subject)
+    }
+}
+
+impl SubjectPattern {
+    /// The pattern as an MQTT topic filter, mapping `*` to `+` and `>` to `#`.
+    pub fn to_mqtt_filter(&self) -> Result<String, ParseError> {
+        join_mqtt(self.tokens().iter().map(|token| match token {
+            Token::Literal(literal) => (literal.as_str(), true),
+            Token::Wildcard => ("+", false),
+            Token::MultiWildcard => ("#", false),
+        }))
+    }
+
+    /// Parses an MQTT topic filter, mapping `+` to `*` and `#` to `>`.
+    pub fn from_mqtt_filter(filter: &str) -> Result<Self, ParseError> {
+        let pattern = split_mqtt(filter, |level| match level {
+            "+" => Ok("*"),
+            "#" => Ok(">"),
+            _ => {
+                validate_mqtt_token(level)?;
+                reject_dot(level)
+            }
+        })?;
+        SubjectPattern::from_str(&pattern)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mqtt_topic_round_trip() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "dev.abc.north.xyz.global.plc-gateway.1.event.alarm",
+        ];
+        for subject_string in subject_strings {
+            let subject = MyceliumSubject::from_str(subject_string).unwrap();
+            let topic = subject.to_mqtt_topic().unwrap();
+            assert_eq!(subject_string.replace('.', "/"), topic);
+            assert_eq!(subject, MyceliumSubject::from_mqtt_topic(&topic).unwrap());
+        }
+    }
+
+    #[test]
+    fn mqtt_topic_fail_unrepresentable_token() {
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc/gateway.1.data").unwrap();
+        let res = subject.to_mqtt_topic().unwrap_err();
+        assert_eq!(
+            &ParseError::InvalidCharacter {
+                token: "plc/gateway".to_string(),
+                character: '/'
+            },
+            res.kind()
+        );
+        assert_eq!(Some(4), res.token_index());
+        assert_eq!(Some(19), res.byte_offset());
+
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data.a+b").unwrap();
+        assert!(subject.to_mqtt_topic().is_err());
+    }
+
+    #[test]
+    fn from_mqtt_topic_fail() {
+        let res =
+            MyceliumSubject::from_mqtt_topic("prod/abc/xyz/local/plc.gateway/1/data").unwrap_err();
+        assert_eq!(
+            &ParseError::InvalidCharacter {
+                token: "plc.gateway".to_string(),
+                character: '.'
+            },
+            res.kind()
+        );
+        assert_eq!(Some(4), res.token_index());
+
+        assert!(MyceliumSubject::from_mqtt_topic("prod/abc/xyz/local/+/1/data").is_err());
+        assert!(MyceliumSubject::from_mqtt_topic("prod/abc/xyz/local/plc-gateway/1/#").is_err());
+    }
+
+    #[test]
+    fn mqtt_filter_round_trip() {
+        let pattern = SubjectPattern::from_str("prod.abc.xyz.local.*.*.data.>").unwrap();
+        let filter = pattern.to_mqtt_filter().unwrap();
+        assert_eq!("prod/abc/xyz/local/+/+/data/#", filter);
+        assert_eq!(pattern, SubjectPattern::from_mqtt_filter(&filter).unwrap());
+
+        assert!(SubjectPattern::from_mqtt_filter("prod/abc/#/local").is_err());
+        assert!(SubjectPattern::from_mqtt_filter("prod/abc/x+y").is_err());
+    }
+}
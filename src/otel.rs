@@ -0,0 +1,82 @@
+use crate::{GeoLocator, MyceliumSubject};
+use alloc::{string::ToString, vec::Vec};
+use opentelemetry::KeyValue;
+
+impl MyceliumSubject {
+    /// Span attributes describing this subject, under the `mycelium.` namespace.
+    ///
+    /// `mycelium.geo` is left out for [`GeoLocator::Omitted`]. `mycelium.payload_path` holds
+    /// the dotted payload identifiers and is only present when there are any.
+    pub fn otel_attributes(&self) -> Vec<KeyValue> {
+        let mut attributes = Vec::from([
+            KeyValue::new("mycelium.environment", self.environment.as_str()),
+            KeyValue::new(
+                "mycelium.enterprise",
+                self.ownership_group.enterprise().to_string(),
+            ),
+            KeyValue::new(
+                "mycelium.op_group",
+                self.ownership_group.op_group().to_string(),
+            ),
+        ]);
+        if self.geo_locator != GeoLocator::Omitted {
+            attributes.push(KeyValue::new("mycelium.geo", self.geo_locator.to_string()));
+        }
+        attributes.extend([
+            KeyValue::new(
+                "mycelium.service",
+                self.service_identifier.service_name().to_string(),
+            ),
+            KeyValue::new(
+                "mycelium.instance",
+                self.service_identifier.instance_id().to_string(),
+            ),
+            KeyValue::new("mycelium.payload_type", self.payload_type.to_string()),
+        ]);
+        if self.payload_identifier.depth() > 0 {
+            attributes.push(KeyValue::new(
+                "mycelium.payload_path",
+                self.payload_identifier.to_string(),
+            ));
+        }
+        attributes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn otel_attributes_global_locator() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.global.plc-gateway.1.event.alarm.high")
+                .unwrap();
+        assert_eq!(
+            vec![
+                KeyValue::new("mycelium.environment", "prod"),
+                KeyValue::new("mycelium.enterprise", "abc"),
+                KeyValue::new("mycelium.op_group", "xyz"),
+                KeyValue::new("mycelium.geo", "global"),
+                KeyValue::new("mycelium.service", "plc-gateway"),
+                KeyValue::new("mycelium.instance", "1"),
+                KeyValue::new("mycelium.payload_type", "event"),
+                KeyValue::new("mycelium.payload_path", "alarm.high"),
+            ],
+            subject.otel_attributes()
+        );
+    }
+
+    #[test]
+    fn otel_attributes_omitted_geo() {
+        let subject = MyceliumSubject::from_str_no_geo("prod.abc.xyz.plc-gateway.1.data").unwrap();
+        let attributes = subject.otel_attributes();
+        assert_eq!(6, attributes.len());
+        assert!(
+            attributes
+                .iter()
+                .all(|attribute| attribute.key.as_str() != "mycelium.geo")
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use crate::{
+    Environment, Locator, MyceliumSubject, OwnershipGroup, ParseError, ServiceIdentifier,
+    split_locator,
+    subject_ref::{FIRST_GEO_INDEX, is_geo_token},
+    validate_iso_code, validate_token,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt::Display, str::FromStr};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub enum Token {
+    Literal(String),
+    /// NATS single-token wildcard `*`.
+    Wildcard,
+    /// NATS multi-token wildcard `>`, only valid as the final token of a pattern.
+    MultiWildcard,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Token::Literal(literal) => write!(f, "{literal}"),
+            Token::Wildcard => write!(f, "*"),
+            Token::MultiWildcard => write!(f, ">"),
+        }
+    }
+}
+
+impl FromStr for Token {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "*" => Ok(Token::Wildcard),
+            ">" => Ok(Token::MultiWildcard),
+            _ => {
+                validate_token(s)?;
+                Ok(Token::Literal(s.to_string()))
+            }
+        }
+    }
+}
+
+/// Number of tokens a geo-locator takes, for building patterns that skip over it.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GeoArity {
+    /// `local`, `global` or `unknown`.
+    Single,
+    /// A full locator with a single region segment: ISO code, region and identifier.
+    Locator,
+}
+
+impl GeoArity {
+    pub fn token_count(self) -> usize {
+        match self {
+            GeoArity::Single => 1,
+            GeoArity::Locator => Locator::MIN_TOKENS,
+        }
+    }
+}
+
+/// A NATS subscription pattern over Mycelium subjects.
+///
+/// Each dot-separated token is either a literal or a wildcard. When the first token is a
+/// literal, it must be a valid [`Environment`]. Parsing lowercases the environment and
+/// uppercases an ISO code, like subjects do: the ISO code is the first token from the fourth
+/// on that can start a geo-locator, when no wildcard comes before it.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct SubjectPattern {
+    tokens: Vec<Token>,
+}
+
+impl SubjectPattern {
+    /// Builds a pattern from tokens that are already known to form a valid pattern.
+    pub(crate) fn from_tokens(tokens: Vec<Token>) -> Self {
+        SubjectPattern { tokens }
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Pattern matching everything a service instance emits from any geo-locator of `arity`.
+    ///
+    /// NATS patterns have a fixed token count, so no single pattern covers both a one-token
+    /// geo-locator and a full locator: `*` stands for exactly one token and `>` may only end
+    /// a pattern. Subscribe once per [`GeoArity`] to cover every region.
+    pub fn any_geo(
+        environment: Environment,
+        ownership_group: &OwnershipGroup,
+        service_identifier: &ServiceIdentifier,
+        arity: GeoArity,
+    ) -> Self {
+        let mut tokens = Vec::new();
+        tokens.push(Token::Literal(environment.to_string()));
+        tokens.extend(
+            ownership_group
+                .levels()
+                .iter()
+                .map(|level| Token::Literal(level.clone())),
+        );
+        tokens.extend((0..arity.token_count()).map(|_| Token::Wildcard));
+        tokens.push(Token::Literal(
+            service_identifier.service_name().to_string(),
+        ));
+        tokens.push(Token::Literal(service_identifier.instance_id().to_string()));
+        tokens.push(Token::MultiWildcard);
+        SubjectPattern { tokens }
+    }
+
+    /// Literals through the payload type of `subject`, followed by `>`.
+    ///
+    /// Like any `>`, it needs at least one more token, so it matches the subjects below the
+    /// payload type but not the bare `...payload_type` subject itself.
+    pub fn with_payload_wildcard(subject: &MyceliumSubject) -> Self {
+        subject.prefix_wildcard(subject.token_count() - subject.payload_identifier.depth())
+    }
+}
+
+/// The all-literal pattern matching exactly `subject`.
+impl From<&MyceliumSubject> for SubjectPattern {
+    fn from(subject: &MyceliumSubject) -> Self {
+        SubjectPattern {
+            tokens: subject
+                .tokens()
+                .map(|token| Token::Literal(token.into_owned()))
+                .collect(),
+        }
+    }
+}
+
+impl Display for SubjectPattern {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (index, token) in self.tokens.iter().enumerate() {
+            if index > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{token}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SubjectPattern {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Vec::new();
+        let mut byte_offset = 0;
+        let mut multi_wildcard_at = None;
+        let mut geo_seen = false;
+        for (index, part) in s.split('.').enumerate() {
+            if let Some((index, byte_offset)) = multi_wildcard_at {
+                return Err(ParseError::MisplacedMultiWildcard.at(index, byte_offset));
+            }
+            let token = Token::from_str(part).map_err(|error| error.at(index, byte_offset))?;
+            let token = match token {
+                Token::Literal(environment) if index == 0 => {
+                    let environment = Environment::from_str(&environment)
+                        .map_err(|error| error.at(index, byte_offset))?;
+                    Token::Literal(environment.to_string())
+                }
+                Token::Literal(literal) if index >= FIRST_GEO_INDEX && !geo_seen => {
+                    geo_seen = is_geo_token(&literal);
+                    if geo_seen && literal.contains('-') {
+                        Token::Literal(literal.to_ascii_uppercase())
+                    } else {
+                        Token::Literal(literal)
+                    }
+                }
+                Token::MultiWildcard => {
+                    multi_wildcard_at = Some((index, byte_offset));
+                    Token::MultiWildcard
+                }
+                token => {
+                    geo_seen |= index >= FIRST_GEO_INDEX;
+                    token
+                }
+            };
+            tokens.push(token);
+            byte_offset += part.len() + 1;
+        }
+        Ok(SubjectPattern { tokens })
+    }
+}
+
+/// A pattern over a [`Locator`], where the region segments and identifier may be wildcards.
+///
+/// The ISO code is always a literal and is validated like in a subject, e.g. `US-CA.*.*`.
+/// A pattern only matches locators with as many region segments as it has.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+pub struct LocatorPattern {
+    iso_3166_2: String,
+    op_region: Vec<Token>,
+    op_identifier: Token,
+}
+
+impl LocatorPattern {
+    pub fn iso_3166_2(&self) -> &str {
+        &self.iso_3166_2
+    }
+
+    pub fn op_region(&self) -> &[Token] {
+        &self.op_region
+    }
+
+    pub fn op_identifier(&self) -> &Token {
+        &self.op_identifier
+    }
+
+    /// Whether `locator` is covered by this pattern.
+    pub fn matches(&self, locator: &Locator) -> bool {
+        let token_matches = |token: &Token, value: &str| match token {
+            Token::Literal(literal) => literal == value,
+            Token::Wildcard | Token::MultiWildcard => true,
+        };
+        self.iso_3166_2 == locator.iso_3166_2()
+            && self.op_region.len() == locator.op_region().len()
+            && self
+                .op_region
+                .iter()
+                .zip(locator.op_region())
+                .all(|(token, segment)| token_matches(token, segment))
+            && token_matches(&self.op_identifier, locator.op_identifier())
+    }
+}
+
+impl Display for LocatorPattern {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.iso_3166_2)?;
+        for token in &self.op_region {
+            write!(f, ".{token}")?;
+        }
+        write!(f, ".{}", self.op_identifier)
+    }
+}
+
+impl FromStr for LocatorPattern {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (iso3166_2_code, op_region, op_identifier) =
+            split_locator(s).ok_or(ParseError::InvalidLocator)?;
+        validate_iso_code(iso3166_2_code)?;
+        let op_region = op_region
+            .split('.')
+            .map(Token::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        let op_identifier = Token::from_str(op_identifier)?;
+        if op_region
+            .iter()
+            .chain([&op_identifier])
+            .any(|token| *token == Token::MultiWildcard)
+        {
+            return Err(ParseError::InvalidLocator);
+        }
+        Ok(LocatorPattern {
+            iso_3166_2: iso3166_2_code.to_ascii_uppercase(),
+            op_region,
+            op_identifier,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pattern_from_string_success() {
+        let pattern_string = "prod.abc.xyz.local.*.*.data";
+        let res = SubjectPattern::from_str(pattern_string).unwrap();
+        assert_eq!(Token::Wildcard, res.tokens()[4]);
+        assert_eq!(Token::Literal("data".to_string()), res.tokens()[6]);
+        assert_eq!(pattern_string, res.to_string());
+    }
+
+    #[test]
+    fn pattern_from_string_wildcard_environment() {
+        let pattern_string = "*.abc.xyz.US-CA.south.abc.plc-gateway.1.data";
+        let res = SubjectPattern::from_str(pattern_string).unwrap();
+        assert_eq!(pattern_string, res.to_string());
+    }
+
+    #[test]
+    fn pattern_from_string_fail_bad_environment() {
+        let res = SubjectPattern::from_str("production.abc.xyz.local.*.*.data");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn pattern_from_string_fail_partial_wildcard() {
+        let res = SubjectPattern::from_str("prod.abc.xyz.local.plc*.*.data").unwrap_err();
+        assert_eq!(
+            &ParseError::InvalidCharacter {
+                token: "plc*".to_string(),
+                character: '*'
+            },
+            res.kind()
+        );
+        assert_eq!(Some(4), res.token_index());
+    }
+
+    #[test]
+    fn pattern_from_string_fail_empty_token() {
+        let res = SubjectPattern::from_str("prod.abc..local.*.*.data");
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn pattern_from_string_multi_wildcard() {
+        let pattern_string = "prod.abc.xyz.local.plc-gateway.1.data.>";
+        let res = SubjectPattern::from_str(pattern_string).unwrap();
+        assert_eq!(Some(&Token::MultiWildcard), res.tokens().last());
+        assert_eq!(pattern_string, res.to_string());
+
+        let res = SubjectPattern::from_str("prod.>").unwrap();
+        assert_eq!("prod.>", res.to_string());
+    }
+
+    #[test]
+    fn pattern_from_string_fail_misplaced_multi_wildcard() {
+        let res = SubjectPattern::from_str("prod.abc.xyz.local.>.1.data").unwrap_err();
+        assert_eq!(&ParseError::MisplacedMultiWildcard, res.kind());
+        assert_eq!(Some(4), res.token_index());
+        assert_eq!(Some(19), res.byte_offset());
+
+        let res = SubjectPattern::from_str("prod.>.>").unwrap_err();
+        assert_eq!(&ParseError::MisplacedMultiWildcard, res.kind());
+
+        let res = SubjectPattern::from_str("prod.abc.xyz.local.plc>.1.data").unwrap_err();
+        assert_eq!(
+            &ParseError::InvalidCharacter {
+                token: "plc>".to_string(),
+                character: '>'
+            },
+            res.kind()
+        );
+    }
+
+    #[test]
+    fn pattern_from_string_normalizes_environment() {
+        let res = SubjectPattern::from_str("PROD.abc.xyz.local.*.*.data").unwrap();
+        assert_eq!("prod.abc.xyz.local.*.*.data", res.to_string());
+    }
+
+    #[test]
+    fn pattern_from_string_normalizes_iso_code() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.us-ca.south.abc.plc-gateway.1.data").unwrap();
+        let res = SubjectPattern::from_str("prod.abc.xyz.us-ca.south.abc.>").unwrap();
+        assert_eq!("prod.abc.xyz.US-CA.south.abc.>", res.to_string());
+        assert!(subject.matches(&res));
+
+        // Only the geo-locator is touched, not later tokens shaped like an ISO code.
+        let res = SubjectPattern::from_str("prod.abc.xyz.local.db-01.*.data.us-ca").unwrap();
+        assert_eq!("prod.abc.xyz.local.db-01.*.data.us-ca", res.to_string());
+        let res = SubjectPattern::from_str("prod.abc.xyz.*.db-01.*.data").unwrap();
+        assert_eq!("prod.abc.xyz.*.db-01.*.data", res.to_string());
+    }
+
+    #[test]
+    fn pattern_any_geo() {
+        let ownership_group = OwnershipGroup::from_str("abc.xyz").unwrap();
+        let service_identifier = ServiceIdentifier::from_str("plc-gateway.1").unwrap();
+        let patterns = [GeoArity::Single, GeoArity::Locator].map(|arity| {
+            SubjectPattern::any_geo(
+                Environment::Production,
+                &ownership_group,
+                &service_identifier,
+                arity,
+            )
+        });
+        assert_eq!("prod.abc.xyz.*.plc-gateway.1.>", patterns[0].to_string());
+        assert_eq!(
+            "prod.abc.xyz.*.*.*.plc-gateway.1.>",
+            patterns[1].to_string()
+        );
+
+        let local = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        let locator =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.value")
+                .unwrap();
+        assert!(local.matches(&patterns[0]));
+        assert!(!local.matches(&patterns[1]));
+        assert!(locator.matches(&patterns[1]));
+        assert!(!locator.matches(&patterns[0]));
+    }
+
+    #[test]
+    fn locator_pattern_matches() {
+        let pattern = LocatorPattern::from_str("US-CA.*.*").unwrap();
+        assert_eq!("US-CA.*.*", pattern.to_string());
+        assert!(pattern.matches(&Locator::from_str("US-CA.south.abc").unwrap()));
+        assert!(!pattern.matches(&Locator::from_str("US-NY.south.abc").unwrap()));
+
+        let pattern = LocatorPattern::from_str("US-CA.*.*.*").unwrap();
+        assert!(pattern.matches(&Locator::from_str("US-CA.north.bay-area.abc").unwrap()));
+        assert!(!pattern.matches(&Locator::from_str("US-CA.south.abc").unwrap()));
+
+        let pattern = LocatorPattern::from_str("US-CA.south.*").unwrap();
+        assert!(pattern.matches(&Locator::from_str("US-CA.south.xyz").unwrap()));
+        assert!(!pattern.matches(&Locator::from_str("US-CA.north.xyz").unwrap()));
+    }
+
+    #[test]
+    fn locator_pattern_fail() {
+        assert!(LocatorPattern::from_str("*.south.abc").is_err());
+        assert!(LocatorPattern::from_str("US-CA.*").is_err());
+        assert!(LocatorPattern::from_str("US-CA.*.>").is_err());
+        #[cfg(feature = "strict-iso")]
+        {
+            let res = LocatorPattern::from_str("US-AA.*.*").unwrap_err();
+            assert_eq!(ParseError::InvalidIsoCode("US-AA".to_string()), res);
+        }
+    }
+
+    #[test]
+    fn pattern_from_subject() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        let exact = SubjectPattern::from(&subject);
+        assert_eq!(subject.to_string(), exact.to_string());
+        assert!(
+            exact
+                .tokens()
+                .iter()
+                .all(|token| matches!(token, Token::Literal(_)))
+        );
+        assert!(subject.matches(&exact));
+
+        let wildcard = SubjectPattern::with_payload_wildcard(&subject);
+        assert_eq!(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.>",
+            wildcard.to_string()
+        );
+        assert!(subject.matches(&wildcard));
+        let bare = subject
+            .truncate_to(crate::SubjectLevel::PayloadType)
+            .unwrap();
+        assert!(!bare.matches(&wildcard));
+    }
+}
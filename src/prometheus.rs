@@ -0,0 +1,55 @@
+use crate::MyceliumSubject;
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+impl MyceliumSubject {
+    /// Metric labels for this subject, in a fixed order.
+    ///
+    /// Prometheus accepts any UTF-8 string as a label value, so tokens are used as-is. Tokens
+    /// hold no whitespace or control characters, so the exposition format only has to escape
+    /// `\` and `"`, which client libraries do. The geo-locator and payload identifiers are
+    /// left out on purpose to keep label cardinality bounded.
+    pub fn prometheus_labels(&self) -> Vec<(&'static str, String)> {
+        Vec::from([
+            ("environment", self.environment.to_string()),
+            ("enterprise", self.ownership_group.enterprise().to_string()),
+            ("op_group", self.ownership_group.op_group().to_string()),
+            (
+                "service_name",
+                self.service_identifier.service_name().to_string(),
+            ),
+            (
+                "instance_id",
+                self.service_identifier.instance_id().to_string(),
+            ),
+            ("payload_type", self.payload_type.to_string()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn prometheus_labels() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("environment", "prod".to_string()),
+                ("enterprise", "abc".to_string()),
+                ("op_group", "xyz".to_string()),
+                ("service_name", "plc-gateway".to_string()),
+                ("instance_id", "1".to_string()),
+                ("payload_type", "data".to_string()),
+            ],
+            subject.prometheus_labels()
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{GeoLocator, MyceliumSubject};
+use alloc::string::String;
+
+const PLACEHOLDER: &str = "***";
+
+/// Which components [`MyceliumSubject::redacted_with`] hides.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct RedactOptions {
+    /// Every ownership level.
+    pub ownership: bool,
+    /// The ISO code, region and identifier of a full locator. `local`, `global` and `unknown`
+    /// are kept.
+    pub locator: bool,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        RedactOptions {
+            ownership: true,
+            locator: false,
+        }
+    }
+}
+
+impl MyceliumSubject {
+    /// The subject with its ownership group hidden, for shared logs.
+    pub fn redacted(&self) -> String {
+        self.redacted_with(RedactOptions::default())
+    }
+
+    /// The subject with the components selected by `options` replaced by `***`.
+    ///
+    /// Each hidden token is replaced on its own, so the output keeps the token count of the
+    /// subject.
+    pub fn redacted_with(&self, options: RedactOptions) -> String {
+        let ownership_end = 1 + self.ownership_group.levels().len();
+        let locator_end = ownership_end
+            + match self.geo_locator {
+                GeoLocator::Locator(_) if options.locator => self.geo_locator.token_count(),
+                _ => 0,
+            };
+        let mut redacted = String::new();
+        for (index, token) in self.tokens().enumerate() {
+            if index > 0 {
+                redacted.push('.');
+            }
+            let hidden = (1..ownership_end).contains(&index) && options.ownership
+                || (ownership_end..locator_end).contains(&index);
+            redacted.push_str(if hidden { PLACEHOLDER } else { &token });
+        }
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    #[test]
+    fn redacted_hides_ownership() {
+        let subject =
+            MyceliumSubject::from_str("prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.value")
+                .unwrap();
+        assert_eq!(
+            "prod.***.***.US-CA.south.abc.plc-gateway.1.data.value",
+            subject.redacted()
+        );
+        assert_eq!(
+            "prod.***.***.***.***.***.plc-gateway.1.data.value",
+            subject.redacted_with(RedactOptions {
+                ownership: true,
+                locator: true
+            })
+        );
+        assert_eq!(
+            "prod.abc.xyz.***.***.***.plc-gateway.1.data.value",
+            subject.redacted_with(RedactOptions {
+                ownership: false,
+                locator: true
+            })
+        );
+    }
+
+    #[test]
+    fn redacted_keeps_local() {
+        let subject =
+            MyceliumSubject::from_str("dev.abc.north.xyz.local.plc-gateway.1.event").unwrap();
+        assert_eq!(
+            "dev.***.***.***.local.plc-gateway.1.event",
+            subject.redacted_with(RedactOptions {
+                ownership: true,
+                locator: true
+            })
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Serialize a [`MyceliumSubject`] as its canonical dotted string.
+//!
+//! ```
+//! use mycelium_uns_rs::MyceliumSubject;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Envelope {
+//!     #[serde(with = "mycelium_uns_rs::serde_string")]
+//!     subject: MyceliumSubject,
+//! }
+//! ```
+
+use crate::MyceliumSubject;
+use alloc::string::String;
+use core::str::FromStr;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{Error, MapAccess, Visitor, value::MapAccessDeserializer},
+};
+
+pub fn serialize<S>(subject: &MyceliumSubject, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(subject)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<MyceliumSubject, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse_subject(&s)
+}
+
+/// Parses `s`, naming the input in the error so a failed config load points at the entry.
+fn parse_subject<E: Error>(s: &str) -> Result<MyceliumSubject, E> {
+    MyceliumSubject::from_str(s).map_err(|error| {
+        E::custom(format_args!(
+            "failed to parse Mycelium subject {s:?}: {error}"
+        ))
+    })
+}
+
+/// A subject that deserializes from either the flat string or the nested object form.
+///
+/// It always serializes as the flat string, which makes it handy while migrating records
+/// stored as objects.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct FlexibleSubject(pub MyceliumSubject);
+
+impl From<FlexibleSubject> for MyceliumSubject {
+    fn from(subject: FlexibleSubject) -> Self {
+        subject.0
+    }
+}
+
+impl Serialize for FlexibleSubject {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+struct FlexibleVisitor;
+
+impl<'de> Visitor<'de> for FlexibleVisitor {
+    type Value = FlexibleSubject;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a Mycelium subject string or object")
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        parse_subject(s).map(FlexibleSubject)
+    }
+
+    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        MyceliumSubject::deserialize(MapAccessDeserializer::new(map)).map(FlexibleSubject)
+    }
+}
+
+impl<'de> Deserialize<'de> for FlexibleSubject {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(FlexibleVisitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Envelope {
+        #[serde(with = "crate::serde_string")]
+        subject: MyceliumSubject,
+        value: u32,
+    }
+
+    #[test]
+    fn serde_string_round_trip() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let envelope = Envelope {
+            subject: MyceliumSubject::from_str(subject_string).unwrap(),
+            value: 42,
+        };
+        let json = serde_json::to_string(&envelope).unwrap();
+        assert_eq!(
+            format!(r#"{{"subject":"{subject_string}","value":42}}"#),
+            json
+        );
+        let res: Envelope = serde_json::from_str(&json).unwrap();
+        assert_eq!(envelope, res);
+    }
+
+    #[test]
+    fn serde_string_fail_bad_subject() {
+        let json = r#"{"subject":"production.abc.xyz.local.plc-gateway.1.data","value":42}"#;
+        let res = serde_json::from_str::<Envelope>(json);
+        assert!(res.is_err());
+
+        let json = r#"{"subject":"prod.abc.xyz.USCA.south.abc.plc-gateway.1.data","value":42}"#;
+        let res = serde_json::from_str::<Envelope>(json).unwrap_err();
+        assert!(
+            res.to_string().starts_with(
+                "failed to parse Mycelium subject \"prod.abc.xyz.USCA.south.abc.plc-gateway.1.data\": \
+                 invalid ISO 3166-2 code \"USCA\" at token 3"
+            ),
+            "{res}"
+        );
+    }
+
+    #[test]
+    fn flexible_subject_both_forms() {
+        let subject = MyceliumSubject::from_str(
+            "prod.abc.north.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+        )
+        .unwrap();
+        let flat = serde_json::to_string(&subject.to_string()).unwrap();
+        let nested = serde_json::to_string(&subject).unwrap();
+        assert!(nested.starts_with('{'));
+
+        let from_flat: FlexibleSubject = serde_json::from_str(&flat).unwrap();
+        let from_nested: FlexibleSubject = serde_json::from_str(&nested).unwrap();
+        assert_eq!(from_flat, from_nested);
+        assert_eq!(subject, from_flat.0);
+        assert_eq!(flat, serde_json::to_string(&from_nested).unwrap());
+
+        let legacy = r#"{"environment":"prod","ownership_group":{"enterprise":"abc","op_group":"xyz"},"geo_locator":"Local","service_identifier":{"service_name":"plc-gateway","instance_id":"1"},"payload_type":"data","payload_identifier":["sensor"]}"#;
+        let from_legacy: FlexibleSubject = serde_json::from_str(legacy).unwrap();
+        assert_eq!(
+            "prod.abc.xyz.local.plc-gateway.1.data.sensor",
+            from_legacy.0.to_string()
+        );
+
+        assert!(serde_json::from_str::<FlexibleSubject>("42").is_err());
+        assert!(serde_json::from_str::<FlexibleSubject>(r#""prod.abc""#).is_err());
+    }
+}
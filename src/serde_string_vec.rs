@@ -0,0 +1,119 @@
+//! Serialize a `Vec<MyceliumSubject>` as a sequence of canonical dotted strings.
+//!
+//! ```
+//! use mycelium_uns_rs::MyceliumSubject;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     #[serde(with = "mycelium_uns_rs::serde_string_vec")]
+//!     subjects: Vec<MyceliumSubject>,
+//! }
+//! ```
+
+use crate::MyceliumSubject;
+use alloc::{string::String, vec::Vec};
+use core::str::FromStr;
+use serde::{
+    Deserializer, Serialize, Serializer,
+    de::{Error, SeqAccess, Visitor},
+};
+
+struct Flat<'a>(&'a MyceliumSubject);
+
+impl Serialize for Flat<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self.0)
+    }
+}
+
+pub fn serialize<S>(subjects: &[MyceliumSubject], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(subjects.iter().map(Flat))
+}
+
+struct SubjectsVisitor;
+
+impl<'de> Visitor<'de> for SubjectsVisitor {
+    type Value = Vec<MyceliumSubject>;
+
+    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "a sequence of Mycelium subject strings")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut subjects = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(s) = seq.next_element::<String>()? {
+            let subject = MyceliumSubject::from_str(&s).map_err(|error| {
+                A::Error::custom(format_args!(
+                    "subject {}: failed to parse Mycelium subject {s:?}: {error}",
+                    subjects.len()
+                ))
+            })?;
+            subjects.push(subject);
+        }
+        Ok(subjects)
+    }
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<MyceliumSubject>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_seq(SubjectsVisitor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Config {
+        #[serde(with = "crate::serde_string_vec")]
+        subjects: Vec<MyceliumSubject>,
+    }
+
+    #[test]
+    fn serde_string_vec_round_trip() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sensor",
+            "prod.abc.xyz.local.plc-gateway.1.heartbeat",
+            "dev.abc.xyz.global.scheduler.2.command.restart",
+        ];
+        let config = Config {
+            subjects: subject_strings
+                .iter()
+                .map(|s| MyceliumSubject::from_str(s).unwrap())
+                .collect(),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            format!(
+                r#"{{"subjects":["{}","{}","{}"]}}"#,
+                subject_strings[0], subject_strings[1], subject_strings[2]
+            ),
+            json
+        );
+        let res: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(config, res);
+    }
+
+    #[test]
+    fn serde_string_vec_fail_reports_element() {
+        let json = r#"{"subjects":["prod.abc.xyz.local.plc-gateway.1.data","production.abc.xyz.local.plc-gateway.1.data"]}"#;
+        let res = serde_json::from_str::<Config>(json).unwrap_err();
+        assert!(
+            res.to_string()
+                .starts_with("subject 1: failed to parse Mycelium subject \"production.abc.xyz.local.plc-gateway.1.data\": invalid environment")
+        );
+    }
+}
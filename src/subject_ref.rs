@@ -0,0 +1,532 @@
+use crate::{
+    Environment, GeoLocator, InstanceId, Locator, MyceliumSubject, OwnershipGroup, ParseError,
+    PayloadPath, PayloadType, SUBJECT_SEPARATOR, ServiceIdentifier, known_payload_type,
+    validate_iso_code, validate_payload_type, validate_token,
+};
+use alloc::{borrow::Cow, string::ToString, vec::Vec};
+use core::{fmt::Display, str::FromStr};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum GeoLocatorRef<'a> {
+    Local,
+    Global,
+    Unknown,
+    Locator {
+        iso_3166_2: &'a str,
+        /// Every region segment, still joined by `.` when there are several.
+        op_region: &'a str,
+        op_identifier: &'a str,
+    },
+}
+
+impl Display for GeoLocatorRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GeoLocatorRef::Local => write!(f, "local"),
+            GeoLocatorRef::Global => write!(f, "global"),
+            GeoLocatorRef::Unknown => write!(f, "unknown"),
+            GeoLocatorRef::Locator {
+                iso_3166_2,
+                op_region,
+                op_identifier,
+            } => write!(f, "{iso_3166_2}.{op_region}.{op_identifier}"),
+        }
+    }
+}
+
+/// Borrowed view of a Mycelium subject.
+///
+/// Parsing validates the subject exactly like [`MyceliumSubject::from_str`], but every
+/// component borrows from the input. The only allocation on success is the list of payload
+/// identifier segments, and none at all without payload identifiers. Components are kept
+/// exactly as written, so the ISO code is only normalized by [`Self::to_owned`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MyceliumSubjectRef<'a> {
+    environment: Environment,
+    ownership_group: &'a str,
+    geo_locator: GeoLocatorRef<'a>,
+    service_name: &'a str,
+    instance_id: &'a str,
+    payload_type: &'a str,
+    payload_identifier: Vec<Cow<'a, str>>,
+}
+
+/// Tokens after the geo-locator that every subject has: service name, instance id and
+/// payload type.
+const TRAILING_TOKENS: usize = ServiceIdentifier::TOKENS + PayloadType::TOKENS;
+/// Index of the first token that may start the geo-locator.
+pub(crate) const FIRST_GEO_INDEX: usize = Environment::TOKENS + OwnershipGroup::MIN_TOKENS;
+
+#[derive(Clone)]
+struct Tokens<'a> {
+    source: &'a str,
+    byte_offset: usize,
+    index: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(source: &'a str) -> Self {
+        Tokens {
+            source,
+            byte_offset: 0,
+            index: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> (usize, usize, &'a str) {
+        self.next()
+            .expect("token count checked before taking tokens")
+    }
+
+    /// Takes the geo-locator tokens, one for `local`, `global` and `unknown` and the ISO code,
+    /// region segments and identifier for a locator.
+    ///
+    /// `found` is the total token count, used to check that the service identifier and payload
+    /// type still fit after the geo-locator. An invalid ISO code goes through `report`.
+    fn geo_locator(
+        &mut self,
+        found: usize,
+        report: &mut impl FnMut(ParseError) -> Result<(), ParseError>,
+    ) -> Result<GeoLocatorRef<'a>, ParseError> {
+        let (index, byte_offset, geo) = self.next_token();
+        match geo {
+            "local" => Ok(GeoLocatorRef::Local),
+            "global" => Ok(GeoLocatorRef::Global),
+            "unknown" => Ok(GeoLocatorRef::Unknown),
+            iso_3166_2 => {
+                let region_len = region_len(self.clone().map(|(_, _, token)| token));
+                let expected = index + Locator::MIN_TOKENS - 1 + region_len + TRAILING_TOKENS;
+                if found < expected {
+                    return Err(ParseError::TooFewTokens { expected, found });
+                }
+                let region_start = self.byte_offset;
+                for _ in 0..region_len {
+                    self.next_token();
+                }
+                let op_region = &self.source[region_start..self.byte_offset - 1];
+                let (_, _, op_identifier) = self.next_token();
+                validate_iso_code(iso_3166_2)
+                    .or_else(|error| report(error.at(index, byte_offset)))?;
+                Ok(GeoLocatorRef::Locator {
+                    iso_3166_2,
+                    op_region,
+                    op_identifier,
+                })
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = (usize, usize, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.source.get(self.byte_offset..)?;
+        let token = rest.split(SUBJECT_SEPARATOR).next()?;
+        let item = (self.index, self.byte_offset, token);
+        self.index += 1;
+        self.byte_offset += token.len() + SUBJECT_SEPARATOR.len_utf8();
+        Some(item)
+    }
+}
+
+/// Whether `token` and the tokens after it read as a whole locator followed by a service
+/// identifier and a payload type: a known ISO code, its region and identifier, then a service
+/// name, an instance id and a valid payload type.
+///
+/// A service name like `db-01` has the shape of an ISO code, so the shape alone is not enough
+/// to tell a stray second geo-locator from a service name.
+pub(crate) fn starts_locator<S: AsRef<str>>(
+    token: &str,
+    after: impl Iterator<Item = S> + Clone,
+) -> bool {
+    if !is_geo_token(token) || validate_iso_code(token).is_err() {
+        return false;
+    }
+    let region_len = region_len(after.clone());
+    let mut after = after.skip(region_len + Locator::MIN_TOKENS - 2 + ServiceIdentifier::TOKENS);
+    after
+        .next()
+        .is_some_and(|payload_type| validate_payload_type(payload_type.as_ref()).is_ok())
+}
+
+/// Number of region segments of a locator, given the tokens that follow its ISO code.
+///
+/// The service name is the first token followed two tokens later by a known payload type,
+/// and the token before it is the identifier. A single segment is assumed otherwise.
+pub(crate) fn region_len<S: AsRef<str>>(after_iso: impl Iterator<Item = S>) -> usize {
+    // A single segment puts the payload type at position 4: region, identifier, service
+    // name, instance id, payload type.
+    after_iso
+        .enumerate()
+        .skip(4)
+        .find(|(_, token)| known_payload_type(token.as_ref()).is_some())
+        .map_or(1, |(position, _)| position - 3)
+}
+
+/// Whether `token` can start the geo-locator, which is what ends the ownership group.
+pub(crate) fn is_geo_token(token: &str) -> bool {
+    match token {
+        "local" | "global" | "unknown" => true,
+        _ => match token.split_once('-') {
+            Some((country, subdivision)) => {
+                country.len() == 2
+                    && country.bytes().all(|b| b.is_ascii_alphabetic())
+                    && (1..=3).contains(&subdivision.len())
+                    && subdivision.bytes().all(|b| b.is_ascii_alphanumeric())
+            }
+            None => false,
+        },
+    }
+}
+
+impl<'a> MyceliumSubjectRef<'a> {
+    pub fn parse(s: &'a str) -> Result<Self, ParseError> {
+        Self::parse_with_max_tokens(s, MyceliumSubject::DEFAULT_MAX_TOKENS)
+    }
+
+    /// Parses like [`Self::parse`], but stops with [`ParseError::TooManyTokens`] as soon as
+    /// `s` turns out to hold more than `max_tokens` tokens.
+    pub fn parse_with_max_tokens(s: &'a str, max_tokens: usize) -> Result<Self, ParseError> {
+        Self::parse_reporting(s, max_tokens, Err)
+    }
+
+    /// Parses a subject straight from `bytes`, borrowing them once they are checked to be
+    /// UTF-8.
+    pub fn from_utf8(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let s = core::str::from_utf8(bytes).map_err(ParseError::InvalidUtf8)?;
+        Self::parse(s)
+    }
+
+    /// Parses `s`, passing every recoverable error to `report`.
+    ///
+    /// When `report` returns the error, parsing stops there. When it returns `Ok`, parsing
+    /// carries on with a placeholder for the failed component, so the caller can collect every
+    /// problem at once; the returned subject is meaningless in that case. Errors that leave
+    /// the token layout unknown, like too few or too many tokens, are always returned.
+    pub(crate) fn parse_reporting(
+        s: &'a str,
+        max_tokens: usize,
+        mut report: impl FnMut(ParseError) -> Result<(), ParseError>,
+    ) -> Result<Self, ParseError> {
+        let mut found = 0;
+        let mut geo_at = None;
+        let mut ownership_ended = false;
+        for (index, byte_offset, token) in Tokens::new(s) {
+            if index == max_tokens {
+                // Stop scanning here: the rest of the input may be arbitrarily long.
+                return Err(ParseError::TooManyTokens {
+                    expected: max_tokens,
+                    found: index + 1,
+                }
+                .at(index, byte_offset));
+            }
+            validate_token(token).or_else(|error| report(error.at(index, byte_offset)))?;
+            if index >= FIRST_GEO_INDEX && geo_at.is_none() && !ownership_ended {
+                if is_geo_token(token) {
+                    geo_at = Some(index);
+                } else if known_payload_type(token).is_some() {
+                    // A payload type before any geo-locator means the geo-locator is mistyped,
+                    // not that the ownership group goes on.
+                    ownership_ended = true;
+                }
+            }
+            found += 1;
+        }
+        if found < MyceliumSubject::MIN_TOKENS {
+            return Err(ParseError::TooFewTokens {
+                expected: MyceliumSubject::MIN_TOKENS,
+                found,
+            });
+        }
+        let geo_at = geo_at
+            .filter(|&index| index + 1 + TRAILING_TOKENS <= found)
+            .unwrap_or(FIRST_GEO_INDEX);
+
+        let mut tokens = Tokens::new(s);
+        let (index, byte_offset, environment) = tokens.next_token();
+        let environment = match Environment::from_str(environment) {
+            Ok(environment) => environment,
+            Err(error) => {
+                report(error.at(index, byte_offset))?;
+                Environment::default()
+            }
+        };
+
+        let ownership_start = tokens.byte_offset;
+        while tokens.index < geo_at {
+            tokens.next_token();
+        }
+        let ownership_group = &s[ownership_start..tokens.byte_offset - 1];
+
+        let geo_locator = tokens.geo_locator(found, &mut report)?;
+
+        let (index, byte_offset, service_name) = tokens.next_token();
+        if starts_locator(service_name, tokens.clone().map(|(_, _, token)| token)) {
+            // A second geo-locator, which would otherwise shift every following token into
+            // the wrong component.
+            report(ParseError::InvalidServiceIdentifier.at(index, byte_offset))?;
+        }
+        let (_, _, instance_id) = tokens.next_token();
+
+        let (index, byte_offset, payload_type) = tokens.next_token();
+        validate_payload_type(payload_type)
+            .or_else(|error| report(error.at(index, byte_offset)))?;
+
+        let depth = found - tokens.index;
+        let max = PayloadPath::DEFAULT_MAX_DEPTH;
+        if let Some((index, byte_offset, _)) = tokens.clone().nth(max) {
+            report(ParseError::TooDeep { depth, max }.at(index, byte_offset))?;
+        }
+
+        Ok(MyceliumSubjectRef {
+            environment,
+            ownership_group,
+            geo_locator,
+            service_name,
+            instance_id,
+            payload_type,
+            payload_identifier: {
+                let mut segments = Vec::with_capacity(depth);
+                segments.extend(tokens.map(|(_, _, segment)| Cow::Borrowed(segment)));
+                segments
+            },
+        })
+    }
+
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    /// The dotted ownership group, as written.
+    pub fn ownership_group(&self) -> &'a str {
+        self.ownership_group
+    }
+
+    pub fn ownership_levels(&self) -> impl Iterator<Item = &'a str> + use<'a> {
+        self.ownership_group.split('.')
+    }
+
+    pub fn enterprise(&self) -> &'a str {
+        self.ownership_levels()
+            .next()
+            .expect("ownership group has at least two levels")
+    }
+
+    pub fn op_group(&self) -> &'a str {
+        self.ownership_group
+            .rsplit('.')
+            .next()
+            .expect("ownership group has at least two levels")
+    }
+
+    pub fn geo_locator(&self) -> GeoLocatorRef<'a> {
+        self.geo_locator
+    }
+
+    pub fn service_name(&self) -> &'a str {
+        self.service_name
+    }
+
+    pub fn instance_id(&self) -> &'a str {
+        self.instance_id
+    }
+
+    pub fn payload_type(&self) -> &'a str {
+        self.payload_type
+    }
+
+    /// The payload identifier segments, borrowed from the input.
+    pub fn payload_identifier(&self) -> &[Cow<'a, str>] {
+        &self.payload_identifier
+    }
+
+    pub fn to_owned(&self) -> MyceliumSubject {
+        let geo_locator = match self.geo_locator {
+            GeoLocatorRef::Local => GeoLocator::Local,
+            GeoLocatorRef::Global => GeoLocator::Global,
+            GeoLocatorRef::Unknown => GeoLocator::Unknown,
+            GeoLocatorRef::Locator {
+                iso_3166_2,
+                op_region,
+                op_identifier,
+            } => GeoLocator::Locator(Locator {
+                iso_3166_2: iso_3166_2.to_ascii_uppercase(),
+                op_region: op_region
+                    .split(SUBJECT_SEPARATOR)
+                    .map(str::to_string)
+                    .collect(),
+                op_identifier: op_identifier.to_string(),
+            }),
+        };
+        MyceliumSubject {
+            environment: self.environment,
+            ownership_group: OwnershipGroup {
+                levels: self.ownership_levels().map(str::to_string).collect(),
+            },
+            geo_locator,
+            service_identifier: ServiceIdentifier {
+                service_name: self.service_name.to_string(),
+                instance_id: InstanceId(self.instance_id.to_string()),
+            },
+            payload_type: PayloadType::from_str(self.payload_type)
+                .expect("payload type validated on parse"),
+            payload_identifier: PayloadPath(
+                self.payload_identifier
+                    .iter()
+                    .map(|segment| segment.to_string())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Display for MyceliumSubjectRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}.{}.{}",
+            self.environment,
+            self.ownership_group,
+            self.geo_locator,
+            self.service_name,
+            self.instance_id,
+            self.payload_type
+        )?;
+        for segment in &self.payload_identifier {
+            write!(f, ".{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn subject_ref_parse_success() {
+        let subject_string =
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value";
+        let res = MyceliumSubjectRef::parse(subject_string).unwrap();
+        assert_eq!(Environment::Production, res.environment());
+        assert_eq!("abc", res.enterprise());
+        assert_eq!("xyz", res.op_group());
+        assert_eq!(
+            GeoLocatorRef::Locator {
+                iso_3166_2: "US-CA",
+                op_region: "south",
+                op_identifier: "abc"
+            },
+            res.geo_locator()
+        );
+        assert_eq!("plc-gateway", res.service_name());
+        assert_eq!("1", res.instance_id());
+        assert_eq!("data", res.payload_type());
+        assert_eq!(
+            ["system", "sub-system", "sensor", "value"],
+            res.payload_identifier()
+        );
+        assert!(
+            res.payload_identifier()
+                .iter()
+                .all(|segment| matches!(segment, Cow::Borrowed(_)))
+        );
+        assert_eq!(subject_string, res.to_string());
+    }
+
+    #[test]
+    fn subject_ref_to_owned() {
+        let subject_strings = [
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "dev.abc.xyz.global.plc-gateway.1.event.alarm",
+        ];
+        for subject_string in subject_strings {
+            let res = MyceliumSubjectRef::parse(subject_string).unwrap();
+            assert_eq!(
+                MyceliumSubject::from_str(subject_string).unwrap(),
+                res.to_owned()
+            );
+        }
+    }
+
+    #[test]
+    fn subject_ref_parse_fail_same_as_owned() {
+        let subject_strings = [
+            "production.abc.xyz.local.plc-gateway.1.data",
+            #[cfg(feature = "strict-iso")]
+            "prod.abc.xyz.US-AA.south.abc.plc-gateway.1.data",
+            "prod.abc.xyz.USCA.south.abc.plc-gateway.1.data",
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway",
+            "prod.abc.xyz.local.plc-gateway.1",
+            #[cfg(not(feature = "custom-payload-types"))]
+            "prod.abc.xyz.local.plc-gateway.1.datas",
+            "prod..xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.local.plc-gateway.1.data.",
+        ];
+        for subject_string in subject_strings {
+            assert_eq!(
+                MyceliumSubject::from_str(subject_string).unwrap_err(),
+                MyceliumSubjectRef::parse(subject_string).unwrap_err()
+            );
+        }
+    }
+
+    #[test]
+    fn subject_ref_parse_three_level_ownership() {
+        let subject_string = "prod.abc.north.xyz.US-CA.south.abc.plc-gateway.1.data.value";
+        let res = MyceliumSubjectRef::parse(subject_string).unwrap();
+        assert_eq!("abc.north.xyz", res.ownership_group());
+        assert_eq!("abc", res.enterprise());
+        assert_eq!("xyz", res.op_group());
+        assert_eq!("US-CA.south.abc", res.geo_locator().to_string());
+        assert_eq!("plc-gateway", res.service_name());
+        assert_eq!(subject_string, res.to_string());
+        assert_eq!(
+            MyceliumSubject::from_str(subject_string).unwrap(),
+            res.to_owned()
+        );
+    }
+
+    #[test]
+    fn subject_ref_parse_locator_without_payload_identifier() {
+        let subject_string = "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data";
+        let res = MyceliumSubjectRef::parse(subject_string).unwrap();
+        assert_eq!("US-CA.south.abc", res.geo_locator().to_string());
+        assert_eq!("plc-gateway", res.service_name());
+        assert_eq!("1", res.instance_id());
+        assert_eq!("data", res.payload_type());
+        assert!(res.payload_identifier().is_empty());
+        assert_eq!(subject_string, res.to_string());
+        assert_eq!(9, res.to_owned().token_count());
+
+        assert_eq!(
+            Err(ParseError::TooFewTokens {
+                expected: 9,
+                found: 8
+            }),
+            MyceliumSubjectRef::parse("prod.abc.xyz.US-CA.south.abc.plc-gateway.1")
+        );
+    }
+
+    #[test]
+    fn subject_ref_parse_hierarchical_region() {
+        let subject_string = "prod.abc.xyz.US-CA.north.bay-area.abc.plc-gateway.1.data.sensor";
+        let res = MyceliumSubjectRef::parse(subject_string).unwrap();
+        assert_eq!(
+            GeoLocatorRef::Locator {
+                iso_3166_2: "US-CA",
+                op_region: "north.bay-area",
+                op_identifier: "abc"
+            },
+            res.geo_locator()
+        );
+        assert_eq!("plc-gateway", res.service_name());
+        assert_eq!(subject_string, res.to_string());
+        assert_eq!(
+            MyceliumSubject::from_str(subject_string).unwrap(),
+            res.to_owned()
+        );
+    }
+}
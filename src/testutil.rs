@@ -0,0 +1,55 @@
+//! Fixtures for crates testing code built on Mycelium subjects.
+
+use crate::{MyceliumSubject, PayloadPath};
+use alloc::{format, string::String, vec::Vec};
+use core::str::FromStr;
+
+/// A representative set of valid subjects.
+///
+/// Covers every geo-locator variant, several payload types, a three-level ownership group,
+/// subjects without payload identifiers, a single-segment payload identifier and one nested
+/// to [`PayloadPath::DEFAULT_MAX_DEPTH`].
+pub fn sample_subjects() -> Vec<MyceliumSubject> {
+    let deepest: String = (0..PayloadPath::DEFAULT_MAX_DEPTH)
+        .map(|depth| format!(".level-{depth}"))
+        .collect();
+    [
+        String::from("prod.abc.xyz.local.plc-gateway.1.data"),
+        String::from("prod.abc.xyz.local.plc-gateway.1.data.value"),
+        String::from("staging.abc.xyz.global.scheduler.a1.command.restart"),
+        String::from("dev.abc.xyz.US-CA.south.abc.plc-gateway.1.heartbeat"),
+        String::from(
+            "prod.abc.xyz.US-CA.south.abc.plc-gateway.1.data.system.sub-system.sensor.value",
+        ),
+        String::from("qa.abc.north.xyz.CA-QC.east.plant-2.historian.3.diagnostics.disk"),
+        String::from("test.abc.xyz.global.alarm-service.7.event.alarm.high"),
+        format!("prod.abc.xyz.local.plc-gateway.1.data{deepest}"),
+    ]
+    .iter()
+    .map(|subject| MyceliumSubject::from_str(subject).expect("sample subjects are valid"))
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn sample_subjects_round_trip() {
+        let samples = sample_subjects();
+        for subject in &samples {
+            assert_eq!(
+                subject,
+                &MyceliumSubject::from_str(&subject.to_string()).unwrap()
+            );
+        }
+        let depths: Vec<usize> = samples
+            .iter()
+            .map(|subject| subject.payload_identifier.depth())
+            .collect();
+        assert!(depths.contains(&0));
+        assert!(depths.contains(&1));
+        assert!(depths.contains(&PayloadPath::DEFAULT_MAX_DEPTH));
+    }
+}
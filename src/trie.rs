@@ -0,0 +1,190 @@
+use crate::{MyceliumSubject, SubjectPattern, Token};
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+
+#[derive(Debug, Clone)]
+struct Node<V> {
+    literals: BTreeMap<String, Node<V>>,
+    wildcard: Option<Box<Node<V>>>,
+    /// Values of patterns ending in `>` right after this node.
+    multi_wildcard: Vec<V>,
+    /// Values of patterns ending at this node.
+    values: Vec<V>,
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Node {
+            literals: BTreeMap::new(),
+            wildcard: None,
+            multi_wildcard: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<V> Node<V> {
+    fn collect<'a>(&'a self, tokens: &[Cow<'_, str>], found: &mut Vec<&'a V>) {
+        let Some((token, rest)) = tokens.split_first() else {
+            found.extend(&self.values);
+            return;
+        };
+        found.extend(&self.multi_wildcard);
+        if let Some(node) = self.literals.get(token.as_ref()) {
+            node.collect(rest, found);
+        }
+        if let Some(node) = &self.wildcard {
+            node.collect(rest, found);
+        }
+    }
+}
+
+/// Subscription patterns indexed by token, to find every pattern matching a subject without
+/// checking them one by one.
+#[derive(Debug, Clone)]
+pub struct SubscriptionTrie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Default for SubscriptionTrie<V> {
+    fn default() -> Self {
+        SubscriptionTrie {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> SubscriptionTrie<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of patterns inserted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds `value` under `pattern`. Inserting the same pattern twice keeps both values.
+    pub fn insert(&mut self, pattern: SubjectPattern, value: V) {
+        let mut node = &mut self.root;
+        for token in pattern.tokens() {
+            match token {
+                Token::Literal(literal) => {
+                    node = node.literals.entry(literal.clone()).or_default();
+                }
+                Token::Wildcard => {
+                    node = node.wildcard.get_or_insert_with(Box::default);
+                }
+                Token::MultiWildcard => {
+                    node.multi_wildcard.push(value);
+                    self.len += 1;
+                    return;
+                }
+            }
+        }
+        node.values.push(value);
+        self.len += 1;
+    }
+
+    /// Values of every pattern matching `subject`, in no particular order.
+    pub fn matching(&self, subject: &MyceliumSubject) -> impl Iterator<Item = &V> {
+        let tokens: Vec<Cow<'_, str>> = subject.tokens().collect();
+        let mut found = Vec::new();
+        self.root.collect(&tokens, &mut found);
+        found.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::str::FromStr;
+
+    fn trie(patterns: &[&'static str]) -> SubscriptionTrie<&'static str> {
+        let mut trie = SubscriptionTrie::new();
+        for pattern in patterns {
+            trie.insert(SubjectPattern::from_str(pattern).unwrap(), *pattern);
+        }
+        trie
+    }
+
+    fn matching(trie: &SubscriptionTrie<&'static str>, subject: &str) -> Vec<&'static str> {
+        let subject = MyceliumSubject::from_str(subject).unwrap();
+        let mut res: Vec<_> = trie.matching(&subject).copied().collect();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn trie_overlapping_wildcards() {
+        let patterns = [
+            "prod.>",
+            "prod.abc.xyz.local.plc-gateway.1.data",
+            "prod.abc.xyz.local.*.*.data",
+            "prod.abc.xyz.local.plc-gateway.1.>",
+            "prod.*.*.local.>",
+            "*.abc.xyz.local.plc-gateway.1.data",
+            "dev.>",
+            "prod.abc.xyz.local.*.*.event",
+        ];
+        let trie = trie(&patterns);
+        assert_eq!(patterns.len(), trie.len());
+
+        let subject = "prod.abc.xyz.local.plc-gateway.1.data";
+        let mut expected: Vec<_> = patterns
+            .iter()
+            .copied()
+            .filter(|pattern| {
+                MyceliumSubject::from_str(subject)
+                    .unwrap()
+                    .matches(&SubjectPattern::from_str(pattern).unwrap())
+            })
+            .collect();
+        expected.sort();
+        assert_eq!(
+            vec![
+                "*.abc.xyz.local.plc-gateway.1.data",
+                "prod.*.*.local.>",
+                "prod.>",
+                "prod.abc.xyz.local.*.*.data",
+                "prod.abc.xyz.local.plc-gateway.1.>",
+                "prod.abc.xyz.local.plc-gateway.1.data",
+            ],
+            expected
+        );
+        assert_eq!(expected, matching(&trie, subject));
+    }
+
+    #[test]
+    fn trie_multi_wildcard_needs_a_token() {
+        let trie = trie(&[
+            "prod.abc.xyz.local.plc-gateway.1.data.>",
+            "prod.abc.xyz.local.plc-gateway.1.data.*",
+        ]);
+        assert!(matching(&trie, "prod.abc.xyz.local.plc-gateway.1.data").is_empty());
+        assert_eq!(
+            vec![
+                "prod.abc.xyz.local.plc-gateway.1.data.*",
+                "prod.abc.xyz.local.plc-gateway.1.data.>",
+            ],
+            matching(&trie, "prod.abc.xyz.local.plc-gateway.1.data.value")
+        );
+        assert_eq!(
+            vec!["prod.abc.xyz.local.plc-gateway.1.data.>"],
+            matching(&trie, "prod.abc.xyz.local.plc-gateway.1.data.system.value")
+        );
+    }
+
+    #[test]
+    fn trie_empty() {
+        let trie = SubscriptionTrie::<()>::new();
+        assert!(trie.is_empty());
+        let subject = MyceliumSubject::from_str("prod.abc.xyz.local.plc-gateway.1.data").unwrap();
+        assert_eq!(0, trie.matching(&subject).count());
+    }
+}